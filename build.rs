@@ -39,9 +39,16 @@ fn copy<U: AsRef<Path>, V: AsRef<Path>>(from: U, to: V) -> std::io::Result<()> {
 
 #[allow(unused_must_use)]
 fn main() -> std::io::Result<()> {
-    let install_path = dirs::config_dir()
-        .expect("Couldn't find a configuration directory to install to.")
-        .join("ttyper");
+    println!("cargo:rerun-if-changed=resources/runtime");
+    println!("cargo:rerun-if-env-changed=TTYPER_CONFIG_DIR");
+    let install_path = match env::var_os("TTYPER_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        // Nowhere to install to; the resources are embedded in the binary anyway
+        None => match dirs::config_dir() {
+            Some(dir) => dir.join("ttyper"),
+            None => return Ok(()),
+        },
+    };
     fs::create_dir_all(&install_path);
 
     let resources_path = env::current_dir()
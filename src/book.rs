@@ -0,0 +1,300 @@
+//! Book mode: typing through a longer text in chunks, keeping its lines and
+//! paragraphs, with a bookmark so the next run continues where the last one stopped.
+
+use crate::test::Break;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// How much of a book each test covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkSize {
+    Words(usize),
+    Sentences(usize),
+}
+
+/// A text split into words, with the line breaks between them.
+#[derive(Debug, Default, PartialEq)]
+pub struct Book {
+    pub words: Vec<String>,
+    /// Break after each word
+    pub breaks: Vec<Break>,
+}
+
+impl Book {
+    /// Split `text` into words, keeping its lines and paragraphs. Typographic
+    /// punctuation is replaced by what a keyboard types, and Markdown formatting is
+    /// removed if `markdown` is set.
+    pub fn parse(text: &str, markdown: bool) -> Book {
+        let text = normalize_punctuation(text);
+        let mut book = Book::default();
+        let mut in_code_block = false;
+        let mut blank_line = false;
+
+        for line in text.lines() {
+            let line = if markdown {
+                match clean_markdown_line(line, &mut in_code_block) {
+                    Some(line) => line,
+                    None => continue,
+                }
+            } else {
+                line.to_owned()
+            };
+
+            let mut words = line.split_whitespace().peekable();
+            if words.peek().is_none() {
+                blank_line = true;
+                continue;
+            }
+            if let Some(last) = book.breaks.last_mut() {
+                *last = if blank_line {
+                    Break::Paragraph
+                } else {
+                    Break::Line
+                };
+            }
+            blank_line = false;
+            for word in words {
+                book.words.push(word.to_owned());
+                book.breaks.push(Break::None);
+            }
+        }
+        book
+    }
+
+    /// The words of the chunk that starts at word `start`.
+    pub fn chunk(&self, start: usize, size: ChunkSize) -> Range<usize> {
+        let start = start.min(self.words.len());
+        let end = match size {
+            ChunkSize::Words(n) => start + n,
+            ChunkSize::Sentences(n) => {
+                let mut sentences = 0;
+                let mut end = start;
+                while end < self.words.len() && sentences < n {
+                    if ends_sentence(&self.words[end]) || self.breaks[end] == Break::Paragraph {
+                        sentences += 1;
+                    }
+                    end += 1;
+                }
+                end
+            }
+        };
+        start..end.min(self.words.len())
+    }
+
+    /// Words and breaks of `range`. The chunk doesn't break after its last word.
+    pub fn slice(&self, range: Range<usize>) -> (Vec<String>, Vec<Break>) {
+        let words = self.words[range.clone()].to_vec();
+        let mut breaks = self.breaks[range].to_vec();
+        if let Some(last) = breaks.last_mut() {
+            *last = Break::None;
+        }
+        (words, breaks)
+    }
+}
+
+/// Whether a word ends a sentence, looking past closing quotes and brackets.
+fn ends_sentence(word: &str) -> bool {
+    word.trim_end_matches(['"', '\'', ')', ']'])
+        .ends_with(['.', '!', '?'])
+}
+
+/// Replace curly quotes, dashes, ellipses and special spaces with plain ASCII, and
+/// drop invisible characters like soft hyphens.
+pub fn normalize_punctuation(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => out.push('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => out.push('"'),
+            '\u{2010}'..='\u{2015}' | '\u{2212}' => out.push('-'),
+            '\u{2026}' => out.push_str("..."),
+            '\u{00A0}' | '\u{2007}' | '\u{2009}' | '\u{202F}' => out.push(' '),
+            '\u{00AD}' | '\u{200B}' | '\u{FEFF}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The text of a Markdown line without its formatting. None for lines that aren't
+/// prose, like code blocks and their fences.
+fn clean_markdown_line(line: &str, in_code_block: &mut bool) -> Option<String> {
+    let trimmed = line.trim();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+        *in_code_block = !*in_code_block;
+        return None;
+    }
+    if *in_code_block || line.starts_with("    ") || line.starts_with('\t') {
+        return None;
+    }
+    // Horizontal rules end a paragraph
+    if trimmed.len() >= 3 && trimmed.chars().all(|c| matches!(c, '-' | '*' | '_' | ' ')) {
+        return Some(String::new());
+    }
+
+    let mut text = trimmed;
+    loop {
+        let before = text;
+        text = text.trim_start_matches('>').trim_start();
+        let hashes = text.len() - text.trim_start_matches('#').len();
+        if hashes > 0 && text[hashes..].starts_with(' ') {
+            text = text[hashes..].trim_start();
+        }
+        for marker in ["- ", "* ", "+ "] {
+            text = text.strip_prefix(marker).unwrap_or(text);
+        }
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 {
+            if let Some(rest) = text[digits..]
+                .strip_prefix(". ")
+                .or_else(|| text[digits..].strip_prefix(") "))
+            {
+                text = rest;
+            }
+        }
+        if text == before {
+            break;
+        }
+    }
+
+    let text = strip_links(text).replace(['*', '`'], "");
+    let words: Vec<String> = text.split_whitespace().map(strip_underscores).collect();
+    Some(words.join(" "))
+}
+
+/// Replace `[text](url)` and `![alt](url)` with their text.
+fn strip_links(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|i| close + i) else {
+            break;
+        };
+        out.push_str(rest[..open].strip_suffix('!').unwrap_or(&rest[..open]));
+        out.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Remove `_` emphasis around a word, keeping underscores inside it.
+fn strip_underscores(word: &str) -> String {
+    let word = word.trim_start_matches('_');
+    let body = word.trim_end_matches(|c: char| c.is_ascii_punctuation() && c != '_');
+    format!("{}{}", body.trim_end_matches('_'), &word[body.len()..])
+}
+
+/// Where the next test of each book starts, by word, keyed by the book's path.
+type Bookmarks = BTreeMap<String, usize>;
+
+fn read_bookmarks(bookmarks_file: &Path) -> Bookmarks {
+    fs::read_to_string(bookmarks_file)
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn book_key(book: &Path) -> String {
+    fs::canonicalize(book)
+        .unwrap_or_else(|_| book.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// The word the next test of `book` starts at, or 0 if it has no bookmark.
+pub fn bookmark(bookmarks_file: &Path, book: &Path) -> usize {
+    read_bookmarks(bookmarks_file)
+        .get(&book_key(book))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Remember that the next test of `book` starts at word `next`.
+pub fn set_bookmark(bookmarks_file: &Path, book: &Path, next: usize) -> Result<(), String> {
+    let mut bookmarks = read_bookmarks(bookmarks_file);
+    bookmarks.insert(book_key(book), next);
+    let content = toml::to_string(&bookmarks).map_err(|e| e.to_string())?;
+    if let Some(dir) = bookmarks_file.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(bookmarks_file, content).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_lines_and_paragraphs() {
+        let book = Book::parse("One two\nthree.\n\n\nFour five.\n", false);
+        assert_eq!(book.words, ["One", "two", "three.", "Four", "five."]);
+        assert_eq!(
+            book.breaks,
+            [
+                Break::None,
+                Break::Line,
+                Break::Paragraph,
+                Break::None,
+                Break::None
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_typographic_punctuation() {
+        assert_eq!(
+            normalize_punctuation("\u{201C}It\u{2019}s\u{201D} \u{2013} fine\u{2026}\u{00A0}ok"),
+            "\"It's\" - fine... ok"
+        );
+    }
+
+    #[test]
+    fn cleans_markdown() {
+        let text = "# Title\n\n> A **bold** and _quiet_ [link](http://x.y) with `code`.\n\n\
+                    ```\nlet x = 1;\n```\n- item one\n1. item_two\n![alt text](img.png)\n";
+        let book = Book::parse(text, true);
+        assert_eq!(
+            book.words.join(" "),
+            "Title A bold and quiet link with code. item one item_two alt text"
+        );
+        assert_eq!(book.breaks[0], Break::Paragraph);
+    }
+
+    #[test]
+    fn chunks_by_words_and_sentences() {
+        let book = Book::parse("One. Two words. \"Three!\" Four\n\nfive six", false);
+        assert_eq!(book.chunk(0, ChunkSize::Words(4)), 0..4);
+        assert_eq!(book.chunk(5, ChunkSize::Words(4)), 5..7);
+        assert_eq!(book.chunk(0, ChunkSize::Sentences(2)), 0..3);
+        // The paragraph ends a sentence without a period
+        assert_eq!(book.chunk(3, ChunkSize::Sentences(2)), 3..5);
+        assert_eq!(book.chunk(9, ChunkSize::Sentences(1)), 7..7);
+
+        let (words, breaks) = book.slice(3..5);
+        assert_eq!(words, ["\"Three!\"", "Four"]);
+        assert_eq!(breaks, [Break::None, Break::None]);
+    }
+
+    #[test]
+    fn bookmarks_are_saved_per_book() {
+        let dir = std::env::temp_dir().join("ttyper_test_bookmarks");
+        let _ = fs::remove_dir_all(&dir);
+        let file = dir.join("bookmarks.toml");
+        let (first, second) = (dir.join("first.txt"), dir.join("second.txt"));
+
+        assert_eq!(bookmark(&file, &first), 0);
+        set_bookmark(&file, &first, 120).unwrap();
+        set_bookmark(&file, &second, 40).unwrap();
+        assert_eq!(bookmark(&file, &first), 120);
+        assert_eq!(bookmark(&file, &second), 40);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,128 @@
+//! Test codes: a short line of text that describes a test completely, so others can
+//! take the same test with `--code`. A code looks like `english200:50:83712:bd:top=100`,
+//! that is language, word count, seed, flags and how the words were chosen; the last
+//! two parts are left out when empty.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Letters for test options that are on or off: `b` no backtracking, `d` sudden
+/// death, `i` case insensitive, `k` no backspace, `s` no shuffle, `l` no limit and
+/// `a` ignore diacritics.
+pub const FLAGS: &str = "bdiksla";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCode {
+    pub language: String,
+    pub words: usize,
+    pub seed: u64,
+    /// Letters from [`FLAGS`] of the options that are on
+    pub flags: String,
+    /// How the words were chosen, as `;`-separated `key=value` pairs like in history
+    pub options: String,
+}
+
+impl TestCode {
+    /// Whether the flag with this letter is on.
+    pub fn flag(&self, letter: char) -> bool {
+        self.flags.contains(letter)
+    }
+
+    /// The `key=value` pairs of the options.
+    pub fn options(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.options
+            .split_terminator(';')
+            .filter_map(|option| option.split_once('='))
+    }
+}
+
+impl fmt::Display for TestCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.language, self.words, self.seed)?;
+        if !self.flags.is_empty() || !self.options.is_empty() {
+            write!(f, ":{}", self.flags)?;
+        }
+        if !self.options.is_empty() {
+            write!(f, ":{}", self.options)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TestCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(5, ':');
+        let language = parts.next().unwrap_or_default();
+        if language.is_empty() {
+            return Err("expected LANGUAGE:WORDS:SEED[:FLAGS[:OPTIONS]]".to_string());
+        }
+        let words = parts
+            .next()
+            .and_then(|words| words.parse().ok())
+            .filter(|&words| words > 0)
+            .ok_or("the word count is missing or not a positive number")?;
+        let seed = parts
+            .next()
+            .and_then(|seed| seed.parse().ok())
+            .ok_or("the seed is missing or not a number")?;
+        let flags = parts.next().unwrap_or_default();
+        if let Some(c) = flags.chars().find(|&c| !FLAGS.contains(c)) {
+            return Err(format!("unknown flag '{}'", c));
+        }
+        let options = parts.next().unwrap_or_default();
+        if let Some(option) = options
+            .split_terminator(';')
+            .find(|option| !option.contains('='))
+        {
+            return Err(format!("expected key=value, got '{}'", option));
+        }
+
+        Ok(TestCode {
+            language: language.to_owned(),
+            words,
+            seed,
+            flags: flags.to_owned(),
+            options: options.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_codes() {
+        for code in [
+            "english200:50:83712",
+            "german:25:1:bd",
+            "english1000:50:7::top=100;min_len=4",
+            "french:10:0:s:letters=a%3Bb",
+        ] {
+            assert_eq!(code.parse::<TestCode>().unwrap().to_string(), code);
+        }
+
+        let code: TestCode = "english1000:30:42:ik:range=10..20".parse().unwrap();
+        assert_eq!(code.language, "english1000");
+        assert_eq!((code.words, code.seed), (30, 42));
+        assert!(code.flag('i') && code.flag('k') && !code.flag('b'));
+        assert_eq!(code.options().collect::<Vec<_>>(), [("range", "10..20")]);
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        for code in [
+            "",
+            "english",
+            "english:0:1",
+            "english:50",
+            "english:50:x",
+            "english:50:1:z",
+            "english:50:1::top",
+        ] {
+            assert!(code.parse::<TestCode>().is_err(), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,893 @@
+//! Key bindings: parsing, matching and the `[key_map]` table.
+//!
+//! A binding is a key with optional modifiers, like `C-S-r`. A sequence of bindings
+//! separated by spaces, like `g g`, is pressed one key after another. Each action
+//! can have several sequences, written as an array.
+
+use crate::test::EditKeys;
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::{de, Deserialize, Serialize, Serializer};
+
+/// A single key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Whether a key press matches. For characters Shift is part of the character,
+    /// so it only changes letters to upper case.
+    pub fn matches(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let without_shift = |m: KeyModifiers| m.difference(KeyModifiers::SHIFT);
+        match (self.code, code) {
+            (KeyCode::Char(c), KeyCode::Char(pressed)) => {
+                let pressed = if modifiers.contains(KeyModifiers::SHIFT) {
+                    pressed.to_uppercase().next().unwrap_or(pressed)
+                } else {
+                    pressed
+                };
+                c == pressed && without_shift(modifiers) == without_shift(self.modifiers)
+            }
+            // Shift-Tab is reported as BackTab, with or without Shift
+            (KeyCode::BackTab, KeyCode::BackTab) => {
+                without_shift(modifiers) == without_shift(self.modifiers)
+            }
+            _ => self.code == code && modifiers == self.modifiers,
+        }
+    }
+
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+}
+
+/// Keys pressed one after another, like `g g`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySequence(pub Vec<KeyBinding>);
+
+impl KeySequence {
+    /// Whether the keys pressed so far match the start of this sequence.
+    fn starts_with(&self, pressed: &[(KeyCode, KeyModifiers)]) -> bool {
+        self.0.len() >= pressed.len()
+            && self
+                .0
+                .iter()
+                .zip(pressed)
+                .all(|(binding, &(code, modifiers))| binding.matches(code, modifiers))
+    }
+}
+
+/// Any of several key sequences for the same action. Written as a single binding or
+/// an array of bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings(pub Vec<KeySequence>);
+
+impl KeyBindings {
+    /// Whether a key press matches one of the single-key bindings.
+    pub fn matches(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        self.0
+            .iter()
+            .any(|sequence| sequence.0.len() == 1 && sequence.starts_with(&[(code, modifiers)]))
+    }
+}
+
+impl From<KeyBinding> for KeyBindings {
+    fn from(binding: KeyBinding) -> Self {
+        KeyBindings(vec![KeySequence(vec![binding])])
+    }
+}
+
+/// Which screen a key is pressed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Test,
+    Results,
+}
+
+/// Actions bound in `[key_map]`, other than the editing keys handled by the test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Abort,
+    Finish,
+    NewTest,
+    RestartTest,
+    Pause,
+    Quit,
+    Restart,
+    Repeat,
+    PracticeMissed,
+    PracticeSlow,
+}
+
+/// Result of looking up a key press.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lookup {
+    Action(Action),
+    /// The keys pressed so far start a longer sequence.
+    Pending,
+    Unbound,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeyMap {
+    // results screen
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub quit: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub restart: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub repeat: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub practice_missed: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub practice_slow: KeyBindings,
+
+    // test screen
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub new_test: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub finish: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub abort: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub submit_word: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub delete_char: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub delete_word: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub restart_test: KeyBindings,
+    #[serde(
+        deserialize_with = "deserialize_keybindings",
+        serialize_with = "serialize_keybindings"
+    )]
+    pub pause: KeyBindings,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bind = |sequences: &[&str]| {
+            KeyBindings(
+                sequences
+                    .iter()
+                    .map(|s| parse_key_sequence(s).expect("default key bindings are valid"))
+                    .collect(),
+            )
+        };
+        Self {
+            quit: bind(&["q"]),
+            restart: bind(&["r"]),
+            repeat: bind(&["t"]),
+            practice_missed: bind(&["p"]),
+            practice_slow: bind(&["s"]),
+
+            new_test: bind(&["Tab"]),
+            finish: bind(&["Esc"]),
+            abort: bind(&["C-c"]),
+            submit_word: bind(&["Space", "Enter"]),
+            delete_char: bind(&["Backspace", "C-h"]),
+            delete_word: bind(&["C-w"]),
+            restart_test: bind(&["C-r"]),
+            pause: bind(&["C-p"]),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Bindings that are active on a screen, with the action they trigger. The
+    /// editing keys have no action since the test handles them itself.
+    fn screen(&self, screen: Screen) -> Vec<(&'static str, Option<Action>, &KeyBindings)> {
+        match screen {
+            Screen::Test => vec![
+                ("abort", Some(Action::Abort), &self.abort),
+                ("finish", Some(Action::Finish), &self.finish),
+                ("new_test", Some(Action::NewTest), &self.new_test),
+                (
+                    "restart_test",
+                    Some(Action::RestartTest),
+                    &self.restart_test,
+                ),
+                ("pause", Some(Action::Pause), &self.pause),
+                ("submit_word", None, &self.submit_word),
+                ("delete_char", None, &self.delete_char),
+                ("delete_word", None, &self.delete_word),
+            ],
+            Screen::Results => vec![
+                ("abort", Some(Action::Abort), &self.abort),
+                ("finish", Some(Action::Finish), &self.finish),
+                ("quit", Some(Action::Quit), &self.quit),
+                ("restart", Some(Action::Restart), &self.restart),
+                ("repeat", Some(Action::Repeat), &self.repeat),
+                (
+                    "practice_missed",
+                    Some(Action::PracticeMissed),
+                    &self.practice_missed,
+                ),
+                (
+                    "practice_slow",
+                    Some(Action::PracticeSlow),
+                    &self.practice_slow,
+                ),
+            ],
+        }
+    }
+
+    /// Look up a key press. `pending` holds the keys of an unfinished sequence and
+    /// is updated. A key that doesn't continue the pending sequence starts over.
+    pub fn press(
+        &self,
+        screen: Screen,
+        pending: &mut Vec<(KeyCode, KeyModifiers)>,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> Lookup {
+        pending.push((code, modifiers));
+        let mut partial = false;
+        for (_, action, bindings) in self.screen(screen) {
+            let Some(action) = action else { continue };
+            for sequence in &bindings.0 {
+                if sequence.starts_with(pending) {
+                    if sequence.0.len() == pending.len() {
+                        pending.clear();
+                        return Lookup::Action(action);
+                    }
+                    partial = true;
+                }
+            }
+        }
+        if partial {
+            return Lookup::Pending;
+        }
+
+        let retry = pending.len() > 1;
+        pending.clear();
+        if retry {
+            self.press(screen, pending, code, modifiers)
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    /// Find keys bound to more than one action on the same screen, bindings that
+    /// can't be used, and test screen actions bound to keys needed for typing.
+    pub fn check_conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        let mut report = |conflict: String| {
+            if !conflicts.contains(&conflict) {
+                conflicts.push(conflict);
+            }
+        };
+
+        for screen in [Screen::Test, Screen::Results] {
+            let bindings = self.screen(screen);
+            for (i, (name, _, sequences)) in bindings.iter().enumerate() {
+                for (other, _, other_sequences) in &bindings[..i] {
+                    for sequence in &sequences.0 {
+                        for other_sequence in &other_sequences.0 {
+                            let (shorter, longer) = if other_sequence.0.len() <= sequence.0.len() {
+                                ((other, other_sequence), (name, sequence))
+                            } else {
+                                ((name, sequence), (other, other_sequence))
+                            };
+                            if !longer.1 .0.starts_with(&shorter.1 .0) {
+                                continue;
+                            }
+                            report(if shorter.1 == longer.1 {
+                                format!(
+                                    "Key conflict: '{}' and '{}' are both bound to {}",
+                                    other,
+                                    name,
+                                    format_key_sequence(sequence)
+                                )
+                            } else {
+                                format!(
+                                    "Key conflict: '{}' ({}) keeps '{}' ({}) from being pressed",
+                                    shorter.0,
+                                    format_key_sequence(shorter.1),
+                                    longer.0,
+                                    format_key_sequence(longer.1)
+                                )
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        for (name, action, bindings) in self.screen(Screen::Test) {
+            for sequence in &bindings.0 {
+                let first = &sequence.0[0];
+                if action.is_none() && sequence.0.len() > 1 {
+                    report(format!(
+                        "'{}' can only use single keys, {} is ignored",
+                        name,
+                        format_key_sequence(sequence)
+                    ));
+                } else if action.is_some()
+                    && matches!(first.code, KeyCode::Char(_))
+                    && first.modifiers.difference(KeyModifiers::SHIFT).is_empty()
+                {
+                    // Plain characters are typed during a test, so they can't trigger actions
+                    report(format!(
+                        "'{}' is bound to {}, which is typed during a test",
+                        name,
+                        format_key_sequence(sequence)
+                    ));
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// The editing keys handled by the test itself.
+    pub fn edit_keys(&self) -> EditKeys {
+        EditKeys {
+            submit_word: self.submit_word.clone(),
+            delete_char: self.delete_char.clone(),
+            delete_word: self.delete_word.clone(),
+        }
+    }
+}
+
+pub fn format_keybinding(binding: &KeyBinding) -> String {
+    let mut parts = Vec::new();
+    if binding.modifiers.contains(KeyModifiers::CONTROL) {
+        parts.push("C".to_string());
+    }
+    if binding.modifiers.contains(KeyModifiers::ALT) {
+        parts.push("A".to_string());
+    }
+    if binding.modifiers.contains(KeyModifiers::SHIFT) || binding.code == KeyCode::BackTab {
+        parts.push("S".to_string());
+    }
+    let key_str = match binding.code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Tab | KeyCode::BackTab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Delete => "Delete".to_string(),
+        KeyCode::Insert => "Insert".to_string(),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PageUp".to_string(),
+        KeyCode::PageDown => "PageDown".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        _ => format!("{:?}", binding.code),
+    };
+    parts.push(key_str);
+    parts.join("-")
+}
+
+pub fn format_key_sequence(sequence: &KeySequence) -> String {
+    let keys: Vec<String> = sequence.0.iter().map(format_keybinding).collect();
+    keys.join(" ")
+}
+
+/// Format all bindings of an action, e.g. `Backspace/C-h`.
+pub fn format_keybindings(bindings: &KeyBindings) -> String {
+    let sequences: Vec<String> = bindings.0.iter().map(format_key_sequence).collect();
+    sequences.join("/")
+}
+
+/// Parse a single key with optional modifiers, like `q`, `C-S-r`, `F5` or `C--`.
+pub fn parse_keybinding(value: &str) -> Result<KeyBinding, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = value;
+    while let Some((prefix, rest)) = key.split_once('-') {
+        let Ok(modifier) = parse_modifier(prefix) else {
+            break;
+        };
+        if rest.is_empty() {
+            break;
+        }
+        modifiers |= modifier;
+        key = rest;
+    }
+
+    let code = match key {
+        "\\-" => KeyCode::Char('-'),
+        _ => parse_key_code(key).map_err(|e| match key.split_once('-') {
+            Some((prefix, _)) if !prefix.is_empty() => parse_modifier(prefix)
+                .err()
+                .unwrap_or_else(|| format!("Invalid keybinding '{}'", value)),
+            _ => e,
+        })?,
+    };
+
+    // Shift is part of the character, so it only makes letters upper case
+    match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+            if !c.is_alphabetic() {
+                return Err(format!(
+                    "Invalid keybinding '{}': S- only works with letters and special keys; use the shifted character instead",
+                    value
+                ));
+            }
+            Ok(KeyBinding {
+                code: KeyCode::Char(c.to_uppercase().next().unwrap_or(c)),
+                modifiers: modifiers.difference(KeyModifiers::SHIFT),
+            })
+        }
+        KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+            Ok(KeyBinding::new(KeyCode::BackTab, modifiers))
+        }
+        code => Ok(KeyBinding { code, modifiers }),
+    }
+}
+
+/// Parse keys separated by spaces, like `g g`.
+pub fn parse_key_sequence(value: &str) -> Result<KeySequence, String> {
+    if value == " " {
+        return Ok(KeySequence(vec![KeyBinding::new(
+            KeyCode::Char(' '),
+            KeyModifiers::NONE,
+        )]));
+    }
+    let keys = value
+        .split_whitespace()
+        .map(parse_keybinding)
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err("Invalid keybinding '': expected a key".to_string());
+    }
+    Ok(KeySequence(keys))
+}
+
+fn parse_modifier(s: &str) -> Result<KeyModifiers, String> {
+    match s {
+        "C" => Ok(KeyModifiers::CONTROL),
+        "A" => Ok(KeyModifiers::ALT),
+        "S" => Ok(KeyModifiers::SHIFT),
+        _ => Err(format!(
+            "Unknown modifier '{}': expected 'C' (Ctrl), 'A' (Alt) or 'S' (Shift)",
+            s
+        )),
+    }
+}
+
+fn parse_key_code(s: &str) -> Result<KeyCode, String> {
+    match s {
+        "Tab" => Ok(KeyCode::Tab),
+        "Backspace" => Ok(KeyCode::Backspace),
+        "Enter" => Ok(KeyCode::Enter),
+        "Esc" => Ok(KeyCode::Esc),
+        "Delete" => Ok(KeyCode::Delete),
+        "Insert" => Ok(KeyCode::Insert),
+        "Space" => Ok(KeyCode::Char(' ')),
+        "Up" => Ok(KeyCode::Up),
+        "Down" => Ok(KeyCode::Down),
+        "Left" => Ok(KeyCode::Left),
+        "Right" => Ok(KeyCode::Right),
+        "Home" => Ok(KeyCode::Home),
+        "End" => Ok(KeyCode::End),
+        "PageUp" => Ok(KeyCode::PageUp),
+        "PageDown" => Ok(KeyCode::PageDown),
+        s if s.chars().count() == 1 => {
+            let c = s.chars().next().unwrap();
+            Ok(KeyCode::Char(c))
+        }
+        s => match s.strip_prefix('F').and_then(|n| n.parse().ok()) {
+            Some(n @ 1..=24) => Ok(KeyCode::F(n)),
+            _ => Err(format!(
+                "Unknown key '{}': expected a single character, F1-F24 or one of Tab, Backspace, Enter, Esc, Delete, Insert, Space, Up, Down, Left, Right, Home, End, PageUp, PageDown",
+                s
+            )),
+        },
+    }
+}
+
+fn deserialize_keybindings<'de, D>(deserializer: D) -> Result<KeyBindings, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let values = match OneOrMany::deserialize(deserializer)
+        .map_err(|_| de::Error::custom("expected a key binding or an array of key bindings"))?
+    {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    };
+    if values.is_empty() {
+        return Err(de::Error::custom("expected at least one key binding"));
+    }
+    values
+        .iter()
+        .map(|value| parse_key_sequence(value))
+        .collect::<Result<_, _>>()
+        .map(KeyBindings)
+        .map_err(de::Error::custom)
+}
+
+fn serialize_keybindings<S: Serializer>(
+    bindings: &KeyBindings,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match &bindings.0[..] {
+        [sequence] => serializer.serialize_str(&format_key_sequence(sequence)),
+        sequences => serializer.collect_seq(sequences.iter().map(format_key_sequence)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn key_map(toml_str: &str) -> KeyMap {
+        toml::from_str::<Config>(toml_str).unwrap().key_map
+    }
+
+    fn bindings(value: &str) -> KeyBindings {
+        KeyBindings(vec![parse_key_sequence(value).unwrap()])
+    }
+
+    #[test]
+    fn parse_simple_char_keybinding() {
+        let kb = parse_keybinding("q").unwrap();
+        assert_eq!(kb.code, KeyCode::Char('q'));
+        assert_eq!(kb.modifiers, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn parse_special_key_keybinding() {
+        let kb = parse_keybinding("Tab").unwrap();
+        assert_eq!(kb.code, KeyCode::Tab);
+        assert_eq!(kb.modifiers, KeyModifiers::NONE);
+
+        let kb = parse_keybinding("Space").unwrap();
+        assert_eq!(kb.code, KeyCode::Char(' '));
+
+        let kb = parse_keybinding("Enter").unwrap();
+        assert_eq!(kb.code, KeyCode::Enter);
+
+        let kb = parse_keybinding("Esc").unwrap();
+        assert_eq!(kb.code, KeyCode::Esc);
+    }
+
+    #[test]
+    fn parse_ctrl_modifier_keybinding() {
+        let kb = parse_keybinding("C-r").unwrap();
+        assert_eq!(kb.code, KeyCode::Char('r'));
+        assert_eq!(kb.modifiers, KeyModifiers::CONTROL);
+    }
+
+    #[test]
+    fn parse_alt_modifier_keybinding() {
+        let kb = parse_keybinding("A-x").unwrap();
+        assert_eq!(kb.code, KeyCode::Char('x'));
+        assert_eq!(kb.modifiers, KeyModifiers::ALT);
+    }
+
+    #[test]
+    fn parse_unicode_char_keybinding() {
+        let kb = parse_keybinding("ü").unwrap();
+        assert_eq!(kb.code, KeyCode::Char('ü'));
+        assert_eq!(kb.modifiers, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn parse_invalid_keybinding() {
+        assert!(parse_keybinding("X-q").is_err());
+        assert!(parse_keybinding("a-b-c").is_err());
+        assert!(parse_keybinding("InvalidKey").is_err());
+        assert!(parse_keybinding("").is_err());
+        assert!(parse_keybinding("F25").is_err());
+        assert!(parse_keybinding("S-1").is_err());
+        assert!(parse_key_sequence("").is_err());
+    }
+
+    #[test]
+    fn parse_combined_modifiers_and_special_keys() {
+        let kb = parse_keybinding("C-S-r").unwrap();
+        assert_eq!(
+            kb,
+            KeyBinding::new(KeyCode::Char('R'), KeyModifiers::CONTROL)
+        );
+        assert!(kb.matches(
+            KeyCode::Char('r'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        ));
+        assert!(kb.matches(
+            KeyCode::Char('R'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        ));
+        assert!(!kb.matches(KeyCode::Char('r'), KeyModifiers::CONTROL));
+
+        let kb = parse_keybinding("C-A-Up").unwrap();
+        assert_eq!(kb.modifiers, KeyModifiers::CONTROL | KeyModifiers::ALT);
+        assert_eq!(kb.code, KeyCode::Up);
+
+        assert_eq!(parse_keybinding("F5").unwrap().code, KeyCode::F(5));
+        assert_eq!(parse_keybinding("F").unwrap().code, KeyCode::Char('F'));
+        assert_eq!(
+            parse_keybinding("PageDown").unwrap().code,
+            KeyCode::PageDown
+        );
+
+        let kb = parse_keybinding("S-Tab").unwrap();
+        assert!(kb.matches(KeyCode::BackTab, KeyModifiers::SHIFT));
+        assert_eq!(format_keybinding(&kb), "S-Tab");
+    }
+
+    #[test]
+    fn parse_minus_key() {
+        let minus = KeyBinding::new(KeyCode::Char('-'), KeyModifiers::NONE);
+        assert_eq!(parse_keybinding("-").unwrap(), minus);
+        assert_eq!(parse_keybinding("\\-").unwrap(), minus);
+
+        let ctrl_minus = KeyBinding::new(KeyCode::Char('-'), KeyModifiers::CONTROL);
+        assert_eq!(parse_keybinding("C--").unwrap(), ctrl_minus);
+        assert_eq!(parse_keybinding("C-\\-").unwrap(), ctrl_minus);
+        assert_eq!(format_keybinding(&ctrl_minus), "C--");
+    }
+
+    #[test]
+    fn keybinding_matches_works() {
+        let kb = KeyBinding {
+            code: KeyCode::Char('q'),
+            modifiers: KeyModifiers::NONE,
+        };
+        assert!(kb.matches(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert!(!kb.matches(KeyCode::Char('r'), KeyModifiers::NONE));
+        assert!(!kb.matches(KeyCode::Char('q'), KeyModifiers::CONTROL));
+
+        let ctrl_r = KeyBinding {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::CONTROL,
+        };
+        assert!(ctrl_r.matches(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        assert!(!ctrl_r.matches(KeyCode::Char('r'), KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn keybinding_ignores_shift_for_characters() {
+        let kb = parse_keybinding("Q").unwrap();
+        assert!(kb.matches(KeyCode::Char('Q'), KeyModifiers::SHIFT));
+        assert!(kb.matches(KeyCode::Char('q'), KeyModifiers::SHIFT));
+        assert!(!kb.matches(KeyCode::Char('Q'), KeyModifiers::CONTROL));
+        assert_eq!(parse_keybinding("S-q").unwrap(), kb);
+        let kb = parse_keybinding("Tab").unwrap();
+        assert!(!kb.matches(KeyCode::Tab, KeyModifiers::SHIFT));
+    }
+
+    #[test]
+    fn keymap_default_values() {
+        let km = KeyMap::default();
+        assert_eq!(km.quit, bindings("q"));
+        assert_eq!(km.restart, bindings("r"));
+        assert_eq!(km.repeat, bindings("t"));
+        assert_eq!(km.practice_missed, bindings("p"));
+        assert_eq!(km.practice_slow, bindings("s"));
+        assert_eq!(km.new_test, bindings("Tab"));
+    }
+
+    #[test]
+    fn keymap_from_toml() {
+        let km = key_map(
+            r#"
+[key_map]
+quit = "x"
+restart = "C-r"
+"#,
+        );
+        assert!(km.quit.matches(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert!(km
+            .restart
+            .matches(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        // unspecified keys keep defaults
+        assert_eq!(km.repeat, bindings("t"));
+    }
+
+    #[test]
+    fn keymap_conflict_detection() {
+        let km = KeyMap::default();
+        assert!(km.check_conflicts().is_empty());
+    }
+
+    #[test]
+    fn keymap_conflict_between_actions() {
+        // create a conflict: quit and restart both bound to 'q'
+        let km = KeyMap {
+            restart: bindings("q"),
+            ..KeyMap::default()
+        };
+        let conflicts = km.check_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("quit"));
+        assert!(conflicts[0].contains("restart"));
+    }
+
+    #[test]
+    fn keymap_conflict_with_finish() {
+        let km = KeyMap {
+            quit: bindings("Esc"),
+            ..KeyMap::default()
+        };
+        let conflicts = km.check_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("'finish' and 'quit'"));
+    }
+
+    #[test]
+    fn keymap_conflict_with_abort() {
+        let km = KeyMap {
+            restart: bindings("C-c"),
+            ..KeyMap::default()
+        };
+        let conflicts = km.check_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("'abort' and 'restart'"));
+    }
+
+    #[test]
+    fn keymap_test_screen_bindings() {
+        let config: Config = toml::from_str(
+            r#"
+[key_map]
+finish = "C-q"
+delete_word = ["C-w", "A-Backspace"]
+pause = "Esc"
+"#,
+        )
+        .unwrap();
+        let km = &config.key_map;
+        assert!(km.finish.matches(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        assert!(km
+            .delete_word
+            .matches(KeyCode::Backspace, KeyModifiers::ALT));
+        assert!(km.submit_word.matches(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(
+            km.check_conflicts().is_empty(),
+            "{:?}",
+            km.check_conflicts()
+        );
+        assert!(config
+            .to_toml()
+            .contains("delete_word = [\"C-w\", \"A-Backspace\"]"));
+
+        assert!(toml::from_str::<Config>("[key_map]\npause = []\n").is_err());
+        assert!(toml::from_str::<Config>("[key_map]\npause = 3\n").is_err());
+    }
+
+    #[test]
+    fn keymap_conflicts_on_test_screen() {
+        let km = KeyMap {
+            restart_test: bindings("Tab"),
+            pause: bindings("p"),
+            delete_word: bindings("C-x w"),
+            ..KeyMap::default()
+        };
+        let conflicts = km.check_conflicts();
+        assert_eq!(
+            conflicts,
+            [
+                "Key conflict: 'new_test' and 'restart_test' are both bound to Tab",
+                "'pause' is bound to p, which is typed during a test",
+                "'delete_word' can only use single keys, C-x w is ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn keymap_sequences() {
+        let km = key_map(
+            r#"
+[key_map]
+quit = ["q", "g g"]
+repeat = "g t"
+"#,
+        );
+        let g = (KeyCode::Char('g'), KeyModifiers::NONE);
+        let press = |pending: &mut Vec<_>, c: char| {
+            km.press(
+                Screen::Results,
+                pending,
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )
+        };
+
+        let mut pending = Vec::new();
+        assert_eq!(press(&mut pending, 'g'), Lookup::Pending);
+        assert_eq!(pending, [g]);
+        assert_eq!(press(&mut pending, 'g'), Lookup::Action(Action::Quit));
+        assert!(pending.is_empty());
+
+        assert_eq!(press(&mut pending, 'g'), Lookup::Pending);
+        assert_eq!(press(&mut pending, 't'), Lookup::Action(Action::Repeat));
+
+        // A key that doesn't continue the sequence is looked up on its own
+        assert_eq!(press(&mut pending, 'g'), Lookup::Pending);
+        assert_eq!(press(&mut pending, 'r'), Lookup::Action(Action::Restart));
+        assert_eq!(press(&mut pending, 'g'), Lookup::Pending);
+        assert_eq!(press(&mut pending, 'x'), Lookup::Unbound);
+        assert!(pending.is_empty());
+
+        assert_eq!(format_keybindings(&km.quit), "q/g g");
+        assert!(km.check_conflicts().is_empty());
+
+        let km = KeyMap {
+            repeat: bindings("g"),
+            ..km
+        };
+        assert_eq!(
+            km.check_conflicts(),
+            ["Key conflict: 'repeat' (g) keeps 'quit' (g g) from being pressed"]
+        );
+    }
+
+    #[test]
+    fn format_keybinding_display() {
+        let kb = KeyBinding {
+            code: KeyCode::Char('q'),
+            modifiers: KeyModifiers::NONE,
+        };
+        assert_eq!(format_keybinding(&kb), "q");
+
+        let kb = KeyBinding {
+            code: KeyCode::Char('r'),
+            modifiers: KeyModifiers::CONTROL,
+        };
+        assert_eq!(format_keybinding(&kb), "C-r");
+
+        let kb = KeyBinding {
+            code: KeyCode::Tab,
+            modifiers: KeyModifiers::NONE,
+        };
+        assert_eq!(format_keybinding(&kb), "Tab");
+
+        let kb = parse_keybinding("C-A-F12").unwrap();
+        assert_eq!(format_keybinding(&kb), "C-A-F12");
+    }
+}
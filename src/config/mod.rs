@@ -0,0 +1,413 @@
+mod keys;
+mod theme;
+mod watch;
+
+use theme::PaletteTable;
+
+pub use keys::{format_keybindings, Action, KeyBindings, KeyMap, Lookup, Screen};
+pub use theme::{supports_truecolor, Theme, ThemeLoader};
+pub use watch::Watcher;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub default_language: String,
+    pub history_file: Option<PathBuf>,
+    /// Treat the ASCII quotes and accents of US-International layouts as dead keys.
+    pub ascii_dead_keys: bool,
+    /// A theme name, or a table of styles that may inherit from a named theme.
+    /// Named themes are applied by `resolve_theme`.
+    #[serde(deserialize_with = "theme::deserialize_theme")]
+    pub theme: Theme,
+    /// Named colors from the `[palette]` table, usable in any style.
+    #[serde(
+        deserialize_with = "theme::deserialize_palette",
+        serialize_with = "theme::serialize_palette",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub palette: BTreeMap<String, Color>,
+    /// Test options used unless a profile or the command line sets them.
+    pub defaults: Profile,
+    pub key_map: KeyMap,
+    /// Named sets of test options from `[profile.<name>]` tables.
+    #[serde(rename = "profile")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Text replaced in the words and typed input, like curly quotes by straight ones.
+    pub substitutions: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_language: "english200".into(),
+            history_file: None,
+            ascii_dead_keys: false,
+            theme: Theme::default(),
+            palette: BTreeMap::new(),
+            defaults: Profile::default(),
+            key_map: KeyMap::default(),
+            profiles: BTreeMap::new(),
+            substitutions: default_substitutions(),
+        }
+    }
+}
+
+/// Curly quotes, which most keyboards can't type, replaced by straight ones.
+fn default_substitutions() -> BTreeMap<String, String> {
+    [
+        ("\u{2018}", "'"),
+        ("\u{2019}", "'"),
+        ("\u{201C}", "\""),
+        ("\u{201D}", "\""),
+    ]
+    .into_iter()
+    .map(|(from, to)| (from.to_string(), to.to_string()))
+    .collect()
+}
+
+/// Commented default configuration written by `ttyper config init`.
+pub const DEFAULT_CONFIG: &str = include_str!("../../resources/config.toml");
+
+/// Result of validating a config file.
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// Problems that keep the config from loading.
+    pub errors: Vec<String>,
+    /// Problems that are ignored when loading, like unknown keys and conflicting bindings.
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// Parse a config file.
+    pub fn parse(source: &str) -> Result<Config, toml::de::Error> {
+        let palette = toml::from_str::<PaletteTable>(source)?.palette;
+        theme::with_palette(&palette, || toml::from_str(source))
+    }
+
+    /// Validate the contents of a config file, and the theme files it uses.
+    pub fn check(source: &str, themes: &dyn ThemeLoader) -> CheckReport {
+        let mut report = CheckReport::default();
+        let mut unknown = Vec::new();
+        let parsed: Result<Config, _> = toml::from_str::<PaletteTable>(source).and_then(|table| {
+            theme::with_palette(&table.palette, || {
+                serde_ignored::deserialize(toml::Deserializer::new(source), |path| {
+                    unknown.push(path.to_string())
+                })
+            })
+        });
+
+        match parsed {
+            Ok(mut config) => {
+                for path in unknown {
+                    report.warnings.push(match locate_key(source, &path) {
+                        Some((line, column)) => {
+                            format!("unknown key '{}' at line {}, column {}", path, line, column)
+                        }
+                        None => format!("unknown key '{}'", path),
+                    });
+                }
+                report.warnings.extend(
+                    config
+                        .key_map
+                        .check_conflicts()
+                        .into_iter()
+                        .map(|conflict| format!("key_map: {}", conflict)),
+                );
+                match config.resolve_theme(None, themes) {
+                    Ok(warnings) => report.warnings.extend(warnings),
+                    Err(e) => report.errors.push(e),
+                }
+            }
+            Err(e) => report.errors.push(e.to_string()),
+        }
+        report
+    }
+
+    /// Apply the named theme the configured theme is based on, or the named theme
+    /// `name` instead of the configured one. Returns warnings about the theme files
+    /// that were read. On error the default theme is left in place.
+    pub fn resolve_theme(
+        &mut self,
+        name: Option<&str>,
+        themes: &dyn ThemeLoader,
+    ) -> Result<Vec<String>, String> {
+        let configured = std::mem::take(&mut self.theme);
+        let (theme, warnings) = match name {
+            Some(name) => Theme::named(name, themes, &self.palette),
+            None => configured.resolve(themes, &self.palette),
+        }?;
+        self.theme = theme;
+        Ok(warnings)
+    }
+
+    /// Serialize the configuration, including every default value.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Config is always representable as TOML")
+    }
+
+    /// Look up a profile by name.
+    pub fn profile(&self, name: &str) -> Result<&Profile, String> {
+        self.profiles.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            if names.is_empty() {
+                format!(
+                    "Error: Profile '{}' not found. No profiles are defined in config.toml.",
+                    name
+                )
+            } else {
+                format!(
+                    "Error: Profile '{}' not found. Available profiles: {}",
+                    name,
+                    names.join(", ")
+                )
+            }
+        })
+    }
+}
+
+/// Test options set by the `[defaults]` table or a `[profile.<name>]` table. Options
+/// left out of a profile fall back to the defaults, and command line flags override
+/// both.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub words: Option<NonZeroUsize>,
+    pub language: Option<String>,
+    pub language_file: Option<PathBuf>,
+    pub no_backtrack: Option<bool>,
+    pub sudden_death: Option<bool>,
+    pub case_insensitive: Option<bool>,
+    pub ignore_diacritics: Option<bool>,
+    pub no_backspace: Option<bool>,
+    pub no_shuffle: Option<bool>,
+    pub no_limit: Option<bool>,
+    pub look_ahead: Option<usize>,
+    pub no_save: Option<bool>,
+}
+
+/// Best-effort 1-based (line, column) of a dotted key path in TOML source.
+fn locate_key(source: &str, path: &str) -> Option<(usize, usize)> {
+    let mut table = String::new();
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let column = line.len() - trimmed.len() + 1;
+        let full = if let Some(header) = trimmed.strip_prefix('[') {
+            table = header
+                .trim_matches(|c| c == '[' || c == ']' || c == ' ')
+                .to_string();
+            table.clone()
+        } else if let Some((key, _)) = trimmed
+            .split_once('=')
+            .filter(|_| !trimmed.starts_with('#'))
+        {
+            let key = key.trim().trim_matches('"');
+            if table.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", table, key)
+            }
+        } else {
+            continue;
+        };
+        if full == path {
+            return Some((i + 1, column));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::theme::format_style;
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn config_default_has_no_history_file() {
+        let config = Config::default();
+        assert!(config.history_file.is_none());
+    }
+
+    #[test]
+    fn config_with_history_file() {
+        let toml_str = r#"history_file = "/custom/path/history.csv""#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.history_file,
+            Some(PathBuf::from("/custom/path/history.csv"))
+        );
+    }
+
+    #[test]
+    fn config_without_history_file() {
+        let toml_str = r#"default_language = "german""#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.history_file.is_none());
+        assert_eq!(config.default_language, "german");
+    }
+
+    #[test]
+    fn config_with_profiles() {
+        let toml_str = r#"
+            [profile.drill]
+            words = 25
+            sudden_death = true
+            look_ahead = 3
+
+            [profile.code]
+            language = "rust"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let drill = config.profile("drill").unwrap();
+        assert_eq!(drill.words, NonZeroUsize::new(25));
+        assert_eq!(drill.sudden_death, Some(true));
+        assert_eq!(drill.look_ahead, Some(3));
+        assert_eq!(drill.no_backtrack, None);
+        assert_eq!(
+            config.profile("code").unwrap().language.as_deref(),
+            Some("rust")
+        );
+
+        let err = config.profile("missing").unwrap_err();
+        assert!(err.contains("Available profiles: code, drill"), "{}", err);
+    }
+
+    #[test]
+    fn default_config_file_matches_defaults() {
+        let report = Config::check(DEFAULT_CONFIG, &HashMap::new());
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(config.to_toml(), Config::default().to_toml());
+    }
+
+    #[test]
+    fn to_toml_roundtrips() {
+        let toml_str = r#"
+            default_language = "german"
+
+            [theme]
+            prompt_correct = "lightgreen:000000;bold;underlined"
+            border_type = "double"
+
+            [key_map]
+            quit = "C-q"
+            new_test = "Space"
+
+            [profile.drill]
+            words = 10
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let reparsed: Config = toml::from_str(&config.to_toml()).unwrap();
+        assert_eq!(reparsed.to_toml(), config.to_toml());
+        assert_eq!(
+            format_style(&reparsed.theme.prompt_correct),
+            "lightgreen:000000;bold;underlined"
+        );
+        assert_eq!(format_keybindings(&reparsed.key_map.new_test), "Space");
+        assert_eq!(
+            reparsed.profile("drill").unwrap().words,
+            NonZeroUsize::new(10)
+        );
+    }
+
+    #[test]
+    fn check_reports_errors_with_position() {
+        let report = Config::check("[theme]\nprompt_correct = \"notacolor\"\n", &HashMap::new());
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("line 2"), "{}", report.errors[0]);
+    }
+
+    #[test]
+    fn check_warns_about_unknown_keys_and_conflicts() {
+        let report = Config::check(
+            "default_langauge = \"german\"\n\n[key_map]\n  quit = \"r\"\n  colour = \"x\"\n",
+            &HashMap::new(),
+        );
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            report.warnings[0],
+            "unknown key 'default_langauge' at line 1, column 1"
+        );
+        assert_eq!(
+            report.warnings[1],
+            "unknown key 'key_map.colour' at line 5, column 3"
+        );
+        assert!(
+            report.warnings[2].starts_with("key_map: "),
+            "{:?}",
+            report.warnings
+        );
+    }
+
+    #[test]
+    fn check_resolves_themes() {
+        let themes = HashMap::from([("plain", "border_type = \"plain\"\ncolour = \"red\"\n")]);
+
+        let report = Config::check("theme = \"plian\"\n", &themes);
+        assert_eq!(
+            report.errors,
+            ["theme 'plian' not found. Available themes: plain"]
+        );
+
+        let report = Config::check(
+            "[theme]\ninherits = \"plain\"\ntitle = \"red\"\ncolour = \"x\"\n",
+            &themes,
+        );
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(
+            report.warnings,
+            [
+                "unknown key 'theme.colour' at line 4, column 1",
+                "unknown key 'colour' in theme 'plain'"
+            ]
+        );
+
+        let mut config: Config = toml::from_str("theme = \"plain\"\n").unwrap();
+        config.resolve_theme(None, &themes).unwrap();
+        assert_eq!(
+            config.theme.border_type,
+            ratatui::widgets::BorderType::Plain
+        );
+        config.resolve_theme(Some("missing"), &themes).unwrap_err();
+        assert_eq!(config.theme.border_type, Theme::default().border_type);
+    }
+
+    #[test]
+    fn palette_colors_in_config() {
+        let config = Config::parse(
+            "[theme]\nprompt_correct = \"accent:shade;bold\"\n\n\
+             [palette]\naccent = \"#fabd2f\"\nshade = \"idx:236\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            theme::format_style(&config.theme.prompt_correct),
+            "fabd2f:idx:236;bold"
+        );
+        assert!(config.to_toml().contains("[palette]\naccent = \"fabd2f\""));
+        assert_eq!(
+            Config::parse(&config.to_toml()).unwrap().to_toml(),
+            config.to_toml()
+        );
+
+        assert!(Config::parse("[palette]\nred = \"#ff0000\"\n").is_err());
+        let report = Config::check("[theme]\ntitle = \"accnet\"\n", &HashMap::new());
+        assert!(report.errors[0].contains("line 2"), "{:?}", report.errors);
+    }
+
+    #[test]
+    fn profile_rejects_unknown_options() {
+        let toml_str = r#"
+            [profile.typo]
+            sudden_deth = true
+        "#;
+        assert!(toml::from_str::<Config>(toml_str).is_err());
+    }
+}
@@ -0,0 +1,1001 @@
+//! Display themes: styles for every part of the UI, loadable from named theme files.
+//!
+//! A theme is a table of style strings, either inline as `[theme]` in config.toml or
+//! in a theme file. Setting `inherits = "<name>"` starts from the named theme and
+//! overrides only the fields given; `theme = "<name>"` uses a named theme as is.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    widgets::BorderType,
+};
+use serde::{
+    de::{self, value::MapAccessDeserializer, IntoDeserializer},
+    Deserialize, Serialize, Serializer,
+};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    /// Named colors styles can use, set by `with_palette` while deserializing.
+    static PALETTE: RefCell<BTreeMap<String, Color>> = RefCell::default();
+}
+
+/// Run `f` with the colors of `palette` available to styles by name.
+pub(super) fn with_palette<T>(palette: &BTreeMap<String, Color>, f: impl FnOnce() -> T) -> T {
+    let previous = PALETTE.with(|p| p.replace(palette.clone()));
+    let result = f();
+    PALETTE.with(|p| p.replace(previous));
+    result
+}
+
+/// Just the `[palette]` table of a config or theme file. Read before the rest of the
+/// file, since styles may use palette colors before the table appears.
+#[derive(Deserialize)]
+pub(super) struct PaletteTable {
+    #[serde(default, deserialize_with = "deserialize_palette")]
+    pub palette: BTreeMap<String, Color>,
+}
+
+/// Looks up named themes, like theme files in the config directory and the built-ins.
+pub trait ThemeLoader {
+    /// Source of the theme file with the given name, if there is one.
+    fn load_theme(&self, name: &str) -> Option<String>;
+
+    /// Names of all themes that can be loaded, sorted.
+    fn theme_names(&self) -> Vec<String>;
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub default: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub title: Style,
+
+    // test widget
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub input_border: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_border: Style,
+
+    #[serde(
+        deserialize_with = "deserialize_border_type",
+        serialize_with = "serialize_border_type"
+    )]
+    pub border_type: BorderType,
+
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_correct: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_incorrect: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_untyped: Style,
+
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_current_correct: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_current_incorrect: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_current_untyped: Style,
+
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub prompt_cursor: Style,
+
+    // results widget
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_overview: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_overview_border: Style,
+
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_worst_keys: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_worst_keys_border: Style,
+
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_chart: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_chart_x: Style,
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_chart_y: Style,
+
+    #[serde(
+        deserialize_with = "deserialize_style",
+        serialize_with = "serialize_style"
+    )]
+    pub results_restart_prompt: Style,
+
+    /// Named theme this one is based on, and the fields it sets itself.
+    #[serde(skip)]
+    base: Option<(String, Vec<String>)>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            default: Style::default(),
+
+            title: Style::default().add_modifier(Modifier::BOLD),
+
+            input_border: Style::default().fg(Color::Cyan),
+            prompt_border: Style::default().fg(Color::Green),
+
+            border_type: BorderType::Rounded,
+
+            prompt_correct: Style::default().fg(Color::Green),
+            prompt_incorrect: Style::default().fg(Color::Red),
+            prompt_untyped: Style::default().fg(Color::Gray),
+
+            prompt_current_correct: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            prompt_current_incorrect: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            prompt_current_untyped: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+
+            prompt_cursor: Style::default().add_modifier(Modifier::UNDERLINED),
+
+            results_overview: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            results_overview_border: Style::default().fg(Color::Cyan),
+
+            results_worst_keys: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            results_worst_keys_border: Style::default().fg(Color::Cyan),
+
+            results_chart: Style::default().fg(Color::Cyan),
+            results_chart_x: Style::default().fg(Color::Cyan),
+            results_chart_y: Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::BOLD),
+
+            results_restart_prompt: Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::ITALIC),
+
+            base: None,
+        }
+    }
+}
+
+impl Theme {
+    /// Load the theme with the given name.
+    pub fn named(
+        name: &str,
+        loader: &dyn ThemeLoader,
+        palette: &BTreeMap<String, Color>,
+    ) -> Result<(Theme, Vec<String>), String> {
+        Theme {
+            base: Some((name.to_string(), Vec::new())),
+            ..Theme::default()
+        }
+        .resolve(loader, palette)
+    }
+
+    /// Apply this theme on top of the theme it is based on, if any. `palette` takes
+    /// precedence over the palettes of theme files. Also returns warnings about
+    /// unknown keys in the theme files that were read.
+    pub fn resolve(
+        self,
+        loader: &dyn ThemeLoader,
+        palette: &BTreeMap<String, Color>,
+    ) -> Result<(Theme, Vec<String>), String> {
+        let mut warnings = Vec::new();
+        let theme = self.rebase(loader, palette, &mut Vec::new(), &mut warnings)?;
+        Ok((theme, warnings))
+    }
+
+    fn rebase(
+        mut self,
+        loader: &dyn ThemeLoader,
+        palette: &BTreeMap<String, Color>,
+        chain: &mut Vec<String>,
+        warnings: &mut Vec<String>,
+    ) -> Result<Theme, String> {
+        let Some((name, keys)) = self.base.take() else {
+            return Ok(self);
+        };
+        if chain.contains(&name) {
+            chain.push(name);
+            return Err(format!("theme inheritance cycle: {}", chain.join(" -> ")));
+        }
+
+        let source = loader.load_theme(&name).ok_or_else(|| {
+            format!(
+                "theme '{}' not found. Available themes: {}",
+                name,
+                loader.theme_names().join(", ")
+            )
+        })?;
+        let in_theme = |e: toml::de::Error| format!("in theme '{}': {}", name, e);
+        let mut file_palette = toml::from_str::<PaletteTable>(&source)
+            .map_err(in_theme)?
+            .palette;
+        file_palette.extend(palette.clone());
+        let ThemeFile(parent) = with_palette(&file_palette, || {
+            serde_ignored::deserialize(toml::Deserializer::new(&source), |path| {
+                let path = path.to_string();
+                if path != "palette" && !path.starts_with("palette.") {
+                    warnings.push(format!("unknown key '{}' in theme '{}'", path, name))
+                }
+            })
+        })
+        .map_err(in_theme)?;
+        chain.push(name);
+        let parent = parent.rebase(loader, palette, chain, warnings)?;
+
+        // Serialized themes list every field, so start from all of the parent's
+        // and replace the ones set here.
+        let mut table = toml::Table::try_from(&parent).expect("Theme is always representable");
+        let own = toml::Table::try_from(&self).expect("Theme is always representable");
+        for key in keys {
+            if let Some(value) = own.get(&key) {
+                table.insert(key, value.clone());
+            }
+        }
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())
+    }
+}
+
+impl Theme {
+    /// Replace 24-bit colors with the closest of the 256 indexed colors, for
+    /// terminals without truecolor support.
+    pub fn downgrade_truecolor(&mut self) {
+        for style in [
+            &mut self.default,
+            &mut self.title,
+            &mut self.input_border,
+            &mut self.prompt_border,
+            &mut self.prompt_correct,
+            &mut self.prompt_incorrect,
+            &mut self.prompt_untyped,
+            &mut self.prompt_current_correct,
+            &mut self.prompt_current_incorrect,
+            &mut self.prompt_current_untyped,
+            &mut self.prompt_cursor,
+            &mut self.results_overview,
+            &mut self.results_overview_border,
+            &mut self.results_worst_keys,
+            &mut self.results_worst_keys_border,
+            &mut self.results_chart,
+            &mut self.results_chart_x,
+            &mut self.results_chart_y,
+            &mut self.results_restart_prompt,
+        ] {
+            style.fg = style.fg.map(downgrade_color);
+            style.bg = style.bg.map(downgrade_color);
+        }
+    }
+}
+
+/// Whether a `COLORTERM` value says the terminal supports 24-bit color.
+pub fn supports_truecolor(colorterm: Option<&str>) -> bool {
+    matches!(colorterm, Some("truecolor" | "24bit"))
+}
+
+/// The closest color of the xterm 256 color palette: the 6x6x6 color cube or the
+/// grayscale ramp. Other colors are left as they are.
+fn downgrade_color(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (v - 35) / 40,
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (
+        LEVELS[ri as usize],
+        LEVELS[gi as usize],
+        LEVELS[bi as usize],
+    );
+
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let gray_index = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + 10 * gray_index;
+
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, cr) + d(g, cg) + d(b, cb)
+    };
+    if distance((gray, gray, gray)) < distance(cube) {
+        Color::Indexed(232 + gray_index)
+    } else {
+        Color::Indexed(16 + 36 * ri + 6 * gi + bi)
+    }
+}
+
+/// A whole theme file.
+struct ThemeFile(Theme);
+
+impl<'de> Deserialize<'de> for ThemeFile {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_theme(deserializer).map(ThemeFile)
+    }
+}
+
+/// Map access that passes everything on except `inherits`, and records the keys it saw.
+struct ThemeMap<A> {
+    inner: A,
+    keys: Vec<String>,
+    inherits: Option<String>,
+}
+
+impl<'de, A: de::MapAccess<'de>> de::MapAccess<'de> for &mut ThemeMap<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        while let Some(key) = self.inner.next_key::<String>()? {
+            if key == "inherits" {
+                self.inherits = Some(self.inner.next_value()?);
+                continue;
+            }
+            self.keys.push(key.clone());
+            return seed
+                .deserialize(IntoDeserializer::<A::Error>::into_deserializer(key))
+                .map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        self.inner.next_value_seed(seed)
+    }
+}
+
+/// Deserialize a table of theme styles, or a theme name as a theme based on it.
+pub(super) fn deserialize_theme<'de, D>(deserializer: D) -> Result<Theme, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct ThemeVisitor;
+    impl<'de> de::Visitor<'de> for ThemeVisitor {
+        type Value = Theme;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a theme name or a table of theme styles")
+        }
+
+        fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+            Ok(Theme {
+                base: Some((name.to_string(), Vec::new())),
+                ..Theme::default()
+            })
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            let mut map = ThemeMap {
+                inner: map,
+                keys: Vec::new(),
+                inherits: None,
+            };
+            let mut theme = Theme::deserialize(MapAccessDeserializer::new(&mut map))?;
+            theme.base = map.inherits.map(|name| (name, map.keys));
+            Ok(theme)
+        }
+    }
+
+    deserializer.deserialize_any(ThemeVisitor)
+}
+
+fn deserialize_style<'de, D>(deserializer: D) -> Result<Style, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct StyleVisitor;
+    impl de::Visitor<'_> for StyleVisitor {
+        type Value = Style;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a string describing a text style")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            let (colors, modifiers) = value.split_once(';').unwrap_or((value, ""));
+            let (fg, bg) = split_colors(colors);
+
+            let mut style = Style {
+                fg: match fg {
+                    "none" | "" => None,
+                    _ => Some(deserialize_color(fg.into_deserializer())?),
+                },
+                bg: match bg {
+                    "none" | "" => None,
+                    _ => Some(deserialize_color(bg.into_deserializer())?),
+                },
+                ..Default::default()
+            };
+
+            for modifier in modifiers.split_terminator(';') {
+                style = style.add_modifier(match modifier {
+                    "bold" => Modifier::BOLD,
+                    "crossed_out" => Modifier::CROSSED_OUT,
+                    "dim" => Modifier::DIM,
+                    "hidden" => Modifier::HIDDEN,
+                    "italic" => Modifier::ITALIC,
+                    "rapid_blink" => Modifier::RAPID_BLINK,
+                    "slow_blink" => Modifier::SLOW_BLINK,
+                    "reversed" => Modifier::REVERSED,
+                    "underlined" => Modifier::UNDERLINED,
+                    _ => {
+                        return Err(E::invalid_value(
+                            de::Unexpected::Str(modifier),
+                            &"a style modifier",
+                        ))
+                    }
+                });
+            }
+
+            Ok(style)
+        }
+    }
+
+    deserializer.deserialize_str(StyleVisitor)
+}
+
+/// Split the colors of a style into foreground and background, keeping `idx:N` together.
+fn split_colors(colors: &str) -> (&str, &str) {
+    let fg_end = match colors.strip_prefix("idx:") {
+        Some(rest) => "idx:".len() + rest.find(':').unwrap_or(rest.len()),
+        None => colors.find(':').unwrap_or(colors.len()),
+    };
+    let (fg, bg) = colors.split_at(fg_end);
+    (fg, bg.strip_prefix(':').unwrap_or("none"))
+}
+
+fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct ColorVisitor;
+    impl de::Visitor<'_> for ColorVisitor {
+        type Value = Color;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            formatter.write_str("a color name, palette color, idx:0-255 or hexadecimal color code")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            match value {
+                "reset" => Ok(Color::Reset),
+                "black" => Ok(Color::Black),
+                "white" => Ok(Color::White),
+                "red" => Ok(Color::Red),
+                "green" => Ok(Color::Green),
+                "yellow" => Ok(Color::Yellow),
+                "blue" => Ok(Color::Blue),
+                "magenta" => Ok(Color::Magenta),
+                "cyan" => Ok(Color::Cyan),
+                "gray" => Ok(Color::Gray),
+                "darkgray" => Ok(Color::DarkGray),
+                "lightred" => Ok(Color::LightRed),
+                "lightgreen" => Ok(Color::LightGreen),
+                "lightyellow" => Ok(Color::LightYellow),
+                "lightblue" => Ok(Color::LightBlue),
+                "lightmagenta" => Ok(Color::LightMagenta),
+                "lightcyan" => Ok(Color::LightCyan),
+                _ => {
+                    if let Some(color) = PALETTE.with(|p| p.borrow().get(value).copied()) {
+                        return Ok(color);
+                    }
+                    if let Some(index) = value.strip_prefix("idx:") {
+                        return index.parse().map(Color::Indexed).map_err(|_| {
+                            E::invalid_value(de::Unexpected::Str(index), &"a color index 0-255")
+                        });
+                    }
+
+                    // Bare hex codes must be six digits, so that words don't read as colors
+                    let (hex, prefixed) = match value.strip_prefix('#') {
+                        Some(hex) => (hex, true),
+                        None => (value, false),
+                    };
+                    let digits: Option<Vec<u8>> = hex
+                        .chars()
+                        .map(|c| c.to_digit(16).map(|d| d as u8))
+                        .collect();
+                    match digits.as_deref() {
+                        Some(&[r1, r2, g1, g2, b1, b2]) => {
+                            Ok(Color::Rgb(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2))
+                        }
+                        Some(&[r, g, b]) if prefixed => Ok(Color::Rgb(r * 17, g * 17, b * 17)),
+                        _ => Err(E::invalid_value(
+                            de::Unexpected::Str(value),
+                            &"a color name, palette color, idx:0-255 or hexadecimal color code",
+                        )),
+                    }
+                }
+            }
+        }
+    }
+
+    deserializer.deserialize_str(ColorVisitor)
+}
+
+/// Deserialize a `[palette]` table of named colors. Names can't be used for the
+/// built-in colors.
+pub(super) fn deserialize_palette<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, Color>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct PaletteColor(Color);
+    impl<'de> Deserialize<'de> for PaletteColor {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_color(deserializer).map(PaletteColor)
+        }
+    }
+
+    let palette = BTreeMap::<String, PaletteColor>::deserialize(deserializer)?;
+    let builtin = |name: &str| {
+        name == "none"
+            || with_palette(&BTreeMap::new(), || {
+                deserialize_color(IntoDeserializer::<de::value::Error>::into_deserializer(
+                    name,
+                ))
+                .is_ok()
+            })
+    };
+    if let Some(name) = palette.keys().find(|name| builtin(name)) {
+        return Err(de::Error::custom(format!(
+            "palette color '{}' would hide a built-in color",
+            name
+        )));
+    }
+    Ok(palette.into_iter().map(|(name, c)| (name, c.0)).collect())
+}
+
+pub(super) fn serialize_palette<S: Serializer>(
+    palette: &BTreeMap<String, Color>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        palette
+            .iter()
+            .map(|(name, &color)| (name, format_color(color))),
+    )
+}
+
+fn deserialize_border_type<'de, D>(deserializer: D) -> Result<BorderType, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct BorderTypeVisitor;
+    impl de::Visitor<'_> for BorderTypeVisitor {
+        type Value = BorderType;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            formatter.write_str("a border type")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            match value {
+                "plain" => Ok(BorderType::Plain),
+                "rounded" => Ok(BorderType::Rounded),
+                "double" => Ok(BorderType::Double),
+                "thick" => Ok(BorderType::Thick),
+                "quadrantinside" => Ok(BorderType::QuadrantInside),
+                "quadrantoutside" => Ok(BorderType::QuadrantOutside),
+                _ => Err(E::invalid_value(
+                    de::Unexpected::Str(value),
+                    &"a border type",
+                )),
+            }
+        }
+    }
+
+    deserializer.deserialize_str(BorderTypeVisitor)
+}
+
+/// Format a color the way `deserialize_color` reads it.
+fn format_color(color: Color) -> String {
+    match color {
+        Color::Reset => "reset".into(),
+        Color::Black => "black".into(),
+        Color::White => "white".into(),
+        Color::Red => "red".into(),
+        Color::Green => "green".into(),
+        Color::Yellow => "yellow".into(),
+        Color::Blue => "blue".into(),
+        Color::Magenta => "magenta".into(),
+        Color::Cyan => "cyan".into(),
+        Color::Gray => "gray".into(),
+        Color::DarkGray => "darkgray".into(),
+        Color::LightRed => "lightred".into(),
+        Color::LightGreen => "lightgreen".into(),
+        Color::LightYellow => "lightyellow".into(),
+        Color::LightBlue => "lightblue".into(),
+        Color::LightMagenta => "lightmagenta".into(),
+        Color::LightCyan => "lightcyan".into(),
+        Color::Rgb(r, g, b) => format!("{:02x}{:02x}{:02x}", r, g, b),
+        Color::Indexed(i) => format!("idx:{}", i),
+    }
+}
+
+/// Format a style in the config style format, e.g. `red:black;bold`.
+pub fn format_style(style: &Style) -> String {
+    let mut out = style.fg.map_or("none".into(), format_color);
+    if let Some(bg) = style.bg {
+        out.push(':');
+        out.push_str(&format_color(bg));
+    }
+    for (name, modifier) in [
+        ("bold", Modifier::BOLD),
+        ("crossed_out", Modifier::CROSSED_OUT),
+        ("dim", Modifier::DIM),
+        ("hidden", Modifier::HIDDEN),
+        ("italic", Modifier::ITALIC),
+        ("rapid_blink", Modifier::RAPID_BLINK),
+        ("slow_blink", Modifier::SLOW_BLINK),
+        ("reversed", Modifier::REVERSED),
+        ("underlined", Modifier::UNDERLINED),
+    ] {
+        if style.add_modifier.contains(modifier) {
+            out.push(';');
+            out.push_str(name);
+        }
+    }
+    out
+}
+
+fn serialize_style<S: Serializer>(style: &Style, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_style(style))
+}
+
+fn serialize_border_type<S: Serializer>(
+    border_type: &BorderType,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(match border_type {
+        BorderType::Plain => "plain",
+        BorderType::Rounded => "rounded",
+        BorderType::Double => "double",
+        BorderType::Thick => "thick",
+        BorderType::QuadrantInside => "quadrantinside",
+        BorderType::QuadrantOutside => "quadrantoutside",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    impl ThemeLoader for HashMap<&str, &str> {
+        fn load_theme(&self, name: &str) -> Option<String> {
+            self.get(name).map(|s| s.to_string())
+        }
+
+        fn theme_names(&self) -> Vec<String> {
+            let mut names: Vec<String> = self.keys().map(|s| s.to_string()).collect();
+            names.sort();
+            names
+        }
+    }
+
+    /// The built-in themes, read from the source tree.
+    struct Builtin;
+
+    impl ThemeLoader for Builtin {
+        fn load_theme(&self, name: &str) -> Option<String> {
+            fs::read_to_string(Builtin::dir().join(format!("{}.toml", name))).ok()
+        }
+
+        fn theme_names(&self) -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(Builtin::dir())
+                .unwrap()
+                .map(|e| {
+                    e.unwrap()
+                        .file_name()
+                        .to_str()
+                        .unwrap()
+                        .replace(".toml", "")
+                })
+                .collect();
+            names.sort();
+            names
+        }
+    }
+
+    impl Builtin {
+        fn dir() -> PathBuf {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/runtime/themes")
+        }
+    }
+
+    fn parse(source: &str) -> Theme {
+        toml::from_str::<ThemeFile>(source).unwrap().0
+    }
+
+    #[test]
+    fn deserializes_basic_colors() {
+        fn color(string: &str) -> Color {
+            deserialize_color(de::IntoDeserializer::<de::value::Error>::into_deserializer(
+                string,
+            ))
+            .expect("failed to deserialize color")
+        }
+
+        assert_eq!(color("black"), Color::Black);
+        assert_eq!(color("000000"), Color::Rgb(0, 0, 0));
+        assert_eq!(color("ffffff"), Color::Rgb(0xff, 0xff, 0xff));
+        assert_eq!(color("FFFFFF"), Color::Rgb(0xff, 0xff, 0xff));
+    }
+
+    #[test]
+    fn deserializes_styles() {
+        fn style(string: &str) -> Style {
+            deserialize_style(de::IntoDeserializer::<de::value::Error>::into_deserializer(
+                string,
+            ))
+            .expect("failed to deserialize style")
+        }
+
+        assert_eq!(style("none"), Style::default());
+        assert_eq!(style("none:none"), Style::default());
+        assert_eq!(style("none:none;"), Style::default());
+
+        assert_eq!(style("black"), Style::default().fg(Color::Black));
+        assert_eq!(
+            style("black:white"),
+            Style::default().fg(Color::Black).bg(Color::White)
+        );
+
+        assert_eq!(
+            style("none;bold"),
+            Style::default().add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            style("none;bold;italic;underlined;"),
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::ITALIC)
+                .add_modifier(Modifier::UNDERLINED)
+        );
+
+        assert_eq!(
+            style("00ff00:000000;bold;dim;italic;slow_blink"),
+            Style::default()
+                .fg(Color::Rgb(0, 0xff, 0))
+                .bg(Color::Rgb(0, 0, 0))
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::DIM)
+                .add_modifier(Modifier::ITALIC)
+                .add_modifier(Modifier::SLOW_BLINK)
+        );
+    }
+
+    #[test]
+    fn deserializes_border_types() {
+        fn border_type(string: &str) -> BorderType {
+            deserialize_border_type(de::IntoDeserializer::<de::value::Error>::into_deserializer(
+                string,
+            ))
+            .expect("failed to deserialize border type")
+        }
+        assert_eq!(border_type("plain"), BorderType::Plain);
+        assert_eq!(border_type("rounded"), BorderType::Rounded);
+        assert_eq!(border_type("double"), BorderType::Double);
+        assert_eq!(border_type("thick"), BorderType::Thick);
+        assert_eq!(border_type("quadrantinside"), BorderType::QuadrantInside);
+        assert_eq!(border_type("quadrantoutside"), BorderType::QuadrantOutside);
+    }
+
+    #[test]
+    fn builtin_themes_load() {
+        let names = Builtin.theme_names();
+        for name in ["colorblind", "dark", "high-contrast", "light"] {
+            assert!(names.iter().any(|n| n == name), "{:?}", names);
+        }
+        for name in &names {
+            let (_, warnings) = Theme::named(name, &Builtin, &BTreeMap::new()).unwrap();
+            assert!(warnings.is_empty(), "{}: {:?}", name, warnings);
+        }
+
+        let (colorblind, _) = Theme::named("colorblind", &Builtin, &BTreeMap::new()).unwrap();
+        let (dark, _) = Theme::named("dark", &Builtin, &BTreeMap::new()).unwrap();
+        assert_eq!(colorblind.default, dark.default);
+        assert_ne!(colorblind.prompt_incorrect, dark.prompt_incorrect);
+    }
+
+    #[test]
+    fn inherits_and_overrides_fields() {
+        let themes = HashMap::from([
+            (
+                "base",
+                "prompt_correct = \"red\"\nborder_type = \"double\"\n",
+            ),
+            ("child", "inherits = \"base\"\nprompt_correct = \"blue\"\n"),
+        ]);
+        let (theme, _) = parse("inherits = \"child\"\ntitle = \"green\"\n")
+            .resolve(&themes, &BTreeMap::new())
+            .unwrap();
+        assert_eq!(theme.prompt_correct, Style::default().fg(Color::Blue));
+        assert_eq!(theme.border_type, BorderType::Double);
+        assert_eq!(theme.title, Style::default().fg(Color::Green));
+        assert_eq!(theme.prompt_incorrect, Theme::default().prompt_incorrect);
+
+        let (named, _) = Theme::named("base", &themes, &BTreeMap::new()).unwrap();
+        assert_eq!(named.prompt_correct, Style::default().fg(Color::Red));
+    }
+
+    #[test]
+    fn reports_missing_themes_cycles_and_unknown_keys() {
+        let themes = HashMap::from([
+            ("a", "inherits = \"b\""),
+            ("b", "inherits = \"a\""),
+            ("typo", "promt_correct = \"red\""),
+            ("broken", "prompt_correct = \"notacolor\""),
+        ]);
+
+        let err = Theme::named("missing", &themes, &BTreeMap::new()).unwrap_err();
+        assert_eq!(
+            err,
+            "theme 'missing' not found. Available themes: a, b, broken, typo"
+        );
+        let err = Theme::named("a", &themes, &BTreeMap::new()).unwrap_err();
+        assert_eq!(err, "theme inheritance cycle: a -> b -> a");
+        let err = Theme::named("broken", &themes, &BTreeMap::new()).unwrap_err();
+        assert!(err.starts_with("in theme 'broken'"), "{}", err);
+        assert!(err.contains("line 1"), "{}", err);
+
+        let (_, warnings) = Theme::named("typo", &themes, &BTreeMap::new()).unwrap();
+        assert_eq!(warnings, ["unknown key 'promt_correct' in theme 'typo'"]);
+    }
+
+    #[test]
+    fn deserializes_indexed_and_short_hex_colors() {
+        fn style(string: &str) -> Result<Style, de::value::Error> {
+            deserialize_style(IntoDeserializer::into_deserializer(string))
+        }
+
+        assert_eq!(
+            style("idx:208").unwrap(),
+            Style::default().fg(Color::Indexed(208))
+        );
+        assert_eq!(
+            style("idx:208:idx:16;bold").unwrap(),
+            Style::default()
+                .fg(Color::Indexed(208))
+                .bg(Color::Indexed(16))
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            style("#ff8700:#f80").unwrap(),
+            Style::default()
+                .fg(Color::Rgb(0xff, 0x87, 0))
+                .bg(Color::Rgb(0xff, 0x88, 0))
+        );
+        assert_eq!(
+            style("black:idx:7").unwrap(),
+            Style::default().fg(Color::Black).bg(Color::Indexed(7))
+        );
+
+        for invalid in [
+            "idx:256",
+            "idx:",
+            "#ff",
+            "fed",
+            "#ggg",
+            "aé123",
+            "red:blue:green",
+        ] {
+            assert!(style(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(format_style(&style("idx:8:#abc").unwrap()), "idx:8:aabbcc");
+    }
+
+    #[test]
+    fn palette_colors_in_theme_files() {
+        let themes = HashMap::from([(
+            "warm",
+            "prompt_correct = \"accent;bold\"\nprompt_incorrect = \"warning\"\n\n\
+             [palette]\naccent = \"#fabd2f\"\nwarning = \"idx:208\"\n",
+        )]);
+        let (theme, warnings) = Theme::named("warm", &themes, &BTreeMap::new()).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            theme.prompt_correct,
+            Style::default()
+                .fg(Color::Rgb(0xfa, 0xbd, 0x2f))
+                .add_modifier(Modifier::BOLD)
+        );
+
+        // The config's palette takes precedence over the theme's
+        let palette = BTreeMap::from([("warning".to_string(), Color::Red)]);
+        let (theme, _) = Theme::named("warm", &themes, &palette).unwrap();
+        assert_eq!(theme.prompt_incorrect, Style::default().fg(Color::Red));
+    }
+
+    #[test]
+    fn downgrades_truecolor() {
+        assert!(supports_truecolor(Some("truecolor")));
+        assert!(!supports_truecolor(Some("256color")));
+        assert!(!supports_truecolor(None));
+
+        assert_eq!(
+            downgrade_color(Color::Rgb(0xff, 0x87, 0)),
+            Color::Indexed(208)
+        );
+        assert_eq!(downgrade_color(Color::Rgb(0, 0, 0)), Color::Indexed(16));
+        assert_eq!(
+            downgrade_color(Color::Rgb(0x80, 0x80, 0x80)),
+            Color::Indexed(244)
+        );
+        assert_eq!(downgrade_color(Color::Green), Color::Green);
+
+        let (mut theme, _) = Theme::named("gruvbox", &Builtin, &BTreeMap::new()).unwrap();
+        theme.downgrade_truecolor();
+        assert!(!format!("{:?}", theme).contains("Rgb"));
+    }
+}
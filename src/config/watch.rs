@@ -0,0 +1,93 @@
+//! Noticing changes to the config file and theme files while ttyper runs.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Modification time and size of a file, or None if it doesn't exist.
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Watches the config file and the theme files in the theme directory by comparing
+/// their modification times and sizes on each check.
+#[derive(Debug)]
+pub struct Watcher {
+    config_file: Option<PathBuf>,
+    theme_dir: Option<PathBuf>,
+    stamps: Vec<(PathBuf, Stamp)>,
+}
+
+impl Watcher {
+    pub fn new(config_file: Option<PathBuf>, theme_dir: Option<PathBuf>) -> Self {
+        let mut watcher = Self {
+            config_file,
+            theme_dir,
+            stamps: Vec::new(),
+        };
+        watcher.stamps = watcher.snapshot();
+        watcher
+    }
+
+    fn snapshot(&self) -> Vec<(PathBuf, Stamp)> {
+        let themes = self
+            .theme_dir
+            .iter()
+            .flat_map(|dir| dir.read_dir().into_iter().flatten().map_while(Result::ok))
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"));
+        let mut files: Vec<PathBuf> = self.config_file.iter().cloned().chain(themes).collect();
+        files[self.config_file.is_some() as usize..].sort();
+
+        files
+            .into_iter()
+            .map(|path| {
+                let stamp = fs::metadata(&path)
+                    .ok()
+                    .and_then(|m| Some((m.modified().ok()?, m.len())));
+                (path, stamp)
+            })
+            .collect()
+    }
+
+    /// Whether a watched file was changed, created or removed since the last check.
+    pub fn changed(&mut self) -> bool {
+        let stamps = self.snapshot();
+        let changed = stamps != self.stamps;
+        self.stamps = stamps;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn notices_changed_created_and_removed_files() {
+        let dir = std::env::temp_dir().join("ttyper_test_watch");
+        let _ = fs::remove_dir_all(&dir);
+        let themes = dir.join("themes");
+        fs::create_dir_all(&themes).unwrap();
+        let config = dir.join("config.toml");
+        fs::write(&config, "default_language = \"english\"\n").unwrap();
+
+        let mut watcher = Watcher::new(Some(config.clone()), Some(themes.clone()));
+        assert!(!watcher.changed());
+
+        let file = fs::File::options().write(true).open(&config).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::write(themes.join("mine.toml"), "title = \"red\"\n").unwrap();
+        assert!(watcher.changed());
+        fs::write(themes.join("notes.txt"), "not a theme\n").unwrap();
+        assert!(!watcher.changed());
+
+        fs::remove_file(&config).unwrap();
+        assert!(watcher.changed());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,333 @@
+//! Editing of recorded history: deleting, tagging, annotating and pruning entries.
+//!
+//! Entries are addressed by ID, their 1-based position among the data rows of the
+//! history file, as shown by `ttyper --history`. IDs shift when earlier entries are
+//! deleted or older ones are imported, so entries can also be addressed by the
+//! datetime they were recorded at, which doesn't change. Rows that can't be parsed
+//! are never selected and are kept as they are.
+
+use super::record::Record;
+use super::{matches_filters, open_locked, read_locked, rewrite_rows, Filters, CSV_FIELDS};
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A history entry given on the command line: its ID or its datetime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryRef {
+    Id(usize),
+    /// `YYYY-MM-DD HH:MM:SS`, as in the `datetime` column
+    Datetime(String),
+}
+
+impl EntryRef {
+    fn matches(&self, id: usize, line: &str) -> bool {
+        match self {
+            EntryRef::Id(n) => *n == id,
+            EntryRef::Datetime(datetime) => line
+                .strip_prefix(datetime.as_str())
+                .is_some_and(|rest| rest.starts_with(',')),
+        }
+    }
+}
+
+impl FromStr for EntryRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = s.parse() {
+            Ok(EntryRef::Id(id))
+        } else if chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").is_ok() {
+            Ok(EntryRef::Datetime(s.to_string()))
+        } else {
+            Err(format!(
+                "expected an entry ID or a datetime like '2026-02-14 10:00:00', found '{}'",
+                s
+            ))
+        }
+    }
+}
+
+impl fmt::Display for EntryRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntryRef::Id(id) => write!(f, "{}", id),
+            EntryRef::Datetime(datetime) => write!(f, "{}", datetime),
+        }
+    }
+}
+
+/// Which entries an edit applies to.
+pub enum Selection<'a> {
+    Entries(&'a [EntryRef]),
+    Filter(&'a Filters<'a>),
+    /// Entries recorded before the given date (YYYY-MM-DD).
+    Before(&'a str),
+}
+
+impl Selection<'_> {
+    fn matches(&self, id: usize, line: &str) -> bool {
+        match self {
+            Selection::Entries(entries) => entries.iter().any(|entry| entry.matches(id, line)),
+            Selection::Filter(filters) => {
+                let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+                matches_filters(&fields, filters)
+            }
+            Selection::Before(date) => line.get(..10).is_some_and(|d| d < *date),
+        }
+    }
+}
+
+/// Run `edit` on the data lines of the locked history file and write them back
+/// if it reports a change.
+fn modify<T>(
+    history_file: &Path,
+    edit: impl FnOnce(&mut Vec<String>) -> Result<(T, bool), String>,
+) -> Result<T, String> {
+    if !history_file.exists() {
+        return Err(format!(
+            "Error: No history found at {}",
+            history_file.display()
+        ));
+    }
+    let io_error = |e: std::io::Error| {
+        format!(
+            "Error: Cannot update history file '{}': {}",
+            history_file.display(),
+            e
+        )
+    };
+
+    let lock = open_locked(history_file).map_err(io_error)?;
+    let content = read_locked(&lock).map_err(io_error)?;
+    let mut lines: Vec<String> = content.lines().skip(1).map(str::to_string).collect();
+
+    let (result, changed) = edit(&mut lines)?;
+    if changed {
+        rewrite_rows(&lock, &lines).map_err(io_error)?;
+    }
+    Ok(result)
+}
+
+/// Find the ID of each entry, checking that it refers to exactly one existing entry.
+fn resolve(entries: &[EntryRef], lines: &[String]) -> Result<Vec<usize>, String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let ids: Vec<usize> = (1..=lines.len())
+                .filter(|&id| entry.matches(id, &lines[id - 1]))
+                .collect();
+            match (entry, &ids[..]) {
+                (_, &[id]) => Ok(id),
+                (EntryRef::Id(id), _) => Err(format!(
+                    "Error: No history entry with ID {} (history has {} entries).",
+                    id,
+                    lines.len()
+                )),
+                (EntryRef::Datetime(datetime), []) => {
+                    Err(format!("Error: No history entry recorded at {}.", datetime))
+                }
+                (EntryRef::Datetime(datetime), _) => Err(format!(
+                    "Error: {} history entries were recorded at {}. Use their IDs instead.",
+                    ids.len(),
+                    datetime
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Apply `update` to the parsed records of the given entries.
+fn update_records(
+    history_file: &Path,
+    entries: &[EntryRef],
+    update: impl Fn(&mut Record),
+) -> Result<(), String> {
+    modify(history_file, |lines| {
+        for id in resolve(entries, lines)? {
+            let line = &mut lines[id - 1];
+            let mut record = Record::parse(line)
+                .map_err(|e| format!("Error: History entry {} is malformed: {}", id, e))?;
+            update(&mut record);
+            *line = record.to_csv_line();
+        }
+        Ok(((), true))
+    })
+}
+
+/// Remove the selected entries and return them with their IDs.
+/// With `dry_run` the history file is left untouched.
+pub fn delete(
+    history_file: &Path,
+    selection: &Selection,
+    dry_run: bool,
+) -> Result<Vec<(usize, Record)>, String> {
+    modify(history_file, |lines| {
+        if let Selection::Entries(entries) = selection {
+            resolve(entries, lines)?;
+        }
+
+        let mut removed = Vec::new();
+        let mut kept = Vec::new();
+        for (i, line) in lines.drain(..).enumerate() {
+            match Record::parse(&line) {
+                Ok(record) if selection.matches(i + 1, &line) => removed.push((i + 1, record)),
+                _ => kept.push(line),
+            }
+        }
+        *lines = kept;
+
+        let changed = !dry_run && !removed.is_empty();
+        Ok((removed, changed))
+    })
+}
+
+/// Add and remove tags on the given entries. Tags already present are not duplicated.
+pub fn tag(
+    history_file: &Path,
+    entries: &[EntryRef],
+    add: &[String],
+    remove: &[String],
+) -> Result<(), String> {
+    update_records(history_file, entries, |record| {
+        record.tags.retain(|t| !remove.contains(t));
+        for tag in add {
+            if !record.tags.contains(tag) {
+                record.tags.push(tag.clone());
+            }
+        }
+    })
+}
+
+/// Set the note of an entry. An empty note removes it.
+pub fn annotate(history_file: &Path, entry: &EntryRef, note: &str) -> Result<(), String> {
+    update_records(history_file, std::slice::from_ref(entry), |record| {
+        record.note = note.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::CSV_HEADER;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn write_history(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ttyper_test_edit_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("history.csv");
+        fs::write(
+            &file,
+            format!(
+                "{}\n{}\n{}\n{}\n{}\n",
+                CSV_HEADER,
+                "2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,a:90%,",
+                "garbage",
+                "2026-02-12 10:00:00,german,50,78.0,74.1,95.0,380,400,,",
+                "2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,,,,,tired,",
+            ),
+        )
+        .unwrap();
+        file
+    }
+
+    fn ids(entries: &[&str]) -> Vec<EntryRef> {
+        entries.iter().map(|e| e.parse().unwrap()).collect()
+    }
+
+    fn datetimes(file: &Path) -> Vec<String> {
+        fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|l| l.split(',').next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn delete_by_ids() {
+        let file = write_history("ids");
+        let removed = delete(&file, &Selection::Entries(&ids(&["1", "4"])), false).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(datetimes(&file), vec!["garbage", "2026-02-12 10:00:00"]);
+        assert!(delete(&file, &Selection::Entries(&ids(&["3"])), false).is_err());
+    }
+
+    #[test]
+    fn delete_by_datetime_is_stable() {
+        let file = write_history("datetime");
+        let entries = ids(&["2026-02-10 10:00:00"]);
+        assert_eq!(
+            delete(&file, &Selection::Entries(&entries), false)
+                .unwrap()
+                .len(),
+            1
+        );
+        // Deleting the same datetime again doesn't hit the entry that took its ID
+        assert!(delete(&file, &Selection::Entries(&entries), false).is_err());
+        assert_eq!(datetimes(&file).len(), 3);
+
+        annotate(&file, &"2026-02-14 10:00:00".parse().unwrap(), "late").unwrap();
+        assert!(annotate(&file, &"2026-02-15 10:00:00".parse().unwrap(), "x").is_err());
+        assert!("yesterday".parse::<EntryRef>().is_err());
+    }
+
+    #[test]
+    fn delete_by_filter_dry_run() {
+        let file = write_history("filter");
+        let filters = Filters {
+            language: Some("english"),
+            since: None,
+            until: None,
+            tag: Some("tired"),
+        };
+        let removed = delete(&file, &Selection::Filter(&filters), true).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].0, 4);
+        assert_eq!(datetimes(&file).len(), 4, "dry run keeps the file");
+    }
+
+    #[test]
+    fn prune_before_date_keeps_malformed_rows() {
+        let file = write_history("prune");
+        let removed = delete(&file, &Selection::Before("2026-02-13"), false).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(datetimes(&file), vec!["garbage", "2026-02-14 10:00:00"]);
+    }
+
+    #[test]
+    fn tag_and_annotate() {
+        let file = write_history("tag");
+        tag(
+            &file,
+            &ids(&["1", "4"]),
+            &["new keyboard".to_string()],
+            &["tired".to_string()],
+        )
+        .unwrap();
+        tag(&file, &ids(&["1"]), &["new keyboard".to_string()], &[]).unwrap();
+        annotate(&file, &EntryRef::Id(1), "after coffee, fast").unwrap();
+
+        let records: Vec<Record> = fs::read_to_string(&file)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .filter_map(|l| Record::parse(l).ok())
+            .collect();
+        assert_eq!(records[0].tags, vec!["new keyboard"]);
+        assert_eq!(records[0].note, "after coffee, fast");
+        assert_eq!(records[2].tags, vec!["new keyboard"]);
+
+        assert!(
+            annotate(&file, &EntryRef::Id(2), "x").is_err(),
+            "malformed row"
+        );
+        assert!(
+            annotate(&file, &EntryRef::Id(9), "x").is_err(),
+            "unknown ID"
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Import and export of history in interoperable formats.
+//!
+//! Exports are either RFC 4180 CSV (quoted where needed) or a JSON array of [`Record`]s.
+//! Imports accept ttyper history files and exports, as well as Monkeytype's CSV and
+//! JSON result exports.
+
+use super::record::Record;
+use super::{open_locked, read_locked, read_shared, rewrite_rows, CSV_HEADER};
+
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Split a CSV line into fields, honouring double-quoted fields.
+fn split_csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Serialize records in the requested format.
+pub fn export(records: &[Record], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(records)
+            .map_err(|e| format!("Error: Cannot serialize history: {}", e)),
+        ExportFormat::Csv => {
+            let mut out = String::from(CSV_HEADER);
+            out.push('\n');
+            for record in records {
+                let line: Vec<String> = record.to_fields().iter().map(|f| csv_escape(f)).collect();
+                out.push_str(&line.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// A result object from Monkeytype's JSON export.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MonkeytypeResult {
+    wpm: f64,
+    raw_wpm: f64,
+    acc: f64,
+    /// Milliseconds since the Unix epoch.
+    timestamp: i64,
+    #[serde(default)]
+    mode: String,
+    #[serde(default)]
+    mode2: serde_json::Value,
+    #[serde(default)]
+    language: Option<String>,
+    /// Correct, incorrect, extra and missed characters.
+    #[serde(default)]
+    char_stats: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonHistory {
+    Ttyper(Vec<Record>),
+    Monkeytype(Vec<MonkeytypeResult>),
+}
+
+impl MonkeytypeResult {
+    /// Convert into a record. Only "words" mode has a word count.
+    fn to_record(&self) -> Option<Record> {
+        let datetime = chrono::DateTime::from_timestamp_millis(self.timestamp)?
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        let words = match (&self.mode[..], &self.mode2) {
+            ("words", serde_json::Value::String(n)) => n.parse().unwrap_or(0),
+            ("words", serde_json::Value::Number(n)) => n.as_u64().unwrap_or(0) as usize,
+            _ => 0,
+        };
+
+        Some(Record {
+            datetime,
+            language: self.language.as_deref().unwrap_or("english").to_string(),
+            words,
+            wpm_raw: self.raw_wpm,
+            wpm_adjusted: self.wpm,
+            accuracy: self.acc,
+            correct: self.char_stats.first().copied().unwrap_or(0),
+            total: self.char_stats.iter().sum(),
+            worst_keys: Vec::new(),
+            missed_words: Vec::new(),
+            avg_dwell_ms: None,
+            key_stats: String::new(),
+            tags: Vec::new(),
+            note: String::new(),
+            options: format!("monkeytype={}", self.timestamp),
+        })
+    }
+}
+
+fn parse_json(content: &str) -> Result<Vec<Record>, String> {
+    match serde_json::from_str(content) {
+        Ok(JsonHistory::Ttyper(records)) => Ok(records),
+        Ok(JsonHistory::Monkeytype(results)) => Ok(results
+            .iter()
+            .filter_map(MonkeytypeResult::to_record)
+            .collect()),
+        Err(_) => Err(
+            "Error: Unrecognized JSON history. Expected a ttyper or Monkeytype export.".to_string(),
+        ),
+    }
+}
+
+fn parse_csv(content: &str) -> Result<Vec<Record>, String> {
+    let mut lines = content.lines();
+    let header = split_csv_fields(lines.next().unwrap_or_default());
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+
+    if column("datetime").is_some() && column("wpm_adjusted").is_some() {
+        // ttyper history or export, in native column order
+        Ok(lines
+            .filter_map(|line| {
+                let fields = split_csv_fields(line);
+                let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+                Record::from_fields(&fields).ok()
+            })
+            .collect())
+    } else if let (Some(timestamp), Some(wpm), Some(raw_wpm), Some(acc)) = (
+        column("timestamp"),
+        column("wpm"),
+        column("rawWpm"),
+        column("acc"),
+    ) {
+        let mode = column("mode");
+        let mode2 = column("mode2");
+        let language = column("language");
+        let char_stats = column("charStats");
+
+        Ok(lines
+            .filter_map(|line| {
+                let fields = split_csv_fields(line);
+                let get = |i: Option<usize>| i.and_then(|i| fields.get(i)).map(String::as_str);
+                MonkeytypeResult {
+                    wpm: get(Some(wpm))?.parse().ok()?,
+                    raw_wpm: get(Some(raw_wpm))?.parse().ok()?,
+                    acc: get(Some(acc))?.parse().ok()?,
+                    timestamp: get(Some(timestamp))?.parse().ok()?,
+                    mode: get(mode).unwrap_or_default().to_string(),
+                    mode2: serde_json::Value::String(get(mode2).unwrap_or_default().to_string()),
+                    language: get(language).filter(|l| !l.is_empty()).map(str::to_string),
+                    char_stats: get(char_stats)
+                        .unwrap_or_default()
+                        .split(';')
+                        .filter_map(|n| n.parse().ok())
+                        .collect(),
+                }
+                .to_record()
+            })
+            .collect())
+    } else {
+        Err("Error: Unrecognized CSV history. Expected a ttyper or Monkeytype export.".to_string())
+    }
+}
+
+/// Parse an import file, detecting JSON or CSV by content.
+pub fn parse_import(content: &str) -> Result<Vec<Record>, String> {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('[') {
+        parse_json(trimmed)
+    } else {
+        parse_csv(content)
+    }
+}
+
+/// Merge incoming records into existing history lines without duplicates.
+/// Existing lines are kept verbatim (even unparseable ones) and the result is
+/// sorted chronologically. Returns the merged data lines and the number added.
+pub fn merge(existing: &[&str], incoming: Vec<Record>) -> (Vec<String>, usize) {
+    let mut seen: HashSet<String> = existing
+        .iter()
+        .filter_map(|line| Record::parse(line).ok())
+        .map(|r| r.identity())
+        .collect();
+
+    let mut merged: Vec<String> = existing.iter().map(|l| l.to_string()).collect();
+    let mut added = 0;
+    for record in incoming {
+        if seen.insert(record.identity()) {
+            merged.push(record.to_csv_line());
+            added += 1;
+        }
+    }
+
+    // Datetimes are "YYYY-MM-DD HH:MM:SS", so the leading field sorts chronologically
+    merged.sort_by(|a, b| {
+        let key = |line: &str| line.split(',').next().unwrap_or_default().to_string();
+        key(a).cmp(&key(b))
+    });
+    (merged, added)
+}
+
+/// Read all parseable records from a history file. A missing file has no records.
+pub fn read_records(history_file: &Path) -> Result<Vec<Record>, String> {
+    if !history_file.exists() {
+        return Ok(Vec::new());
+    }
+    let content = read_shared(history_file).map_err(|e| {
+        format!(
+            "Error: Cannot read history file '{}': {}",
+            history_file.display(),
+            e
+        )
+    })?;
+    Ok(content
+        .lines()
+        .skip(1)
+        .filter_map(|line| Record::parse(line).ok())
+        .collect())
+}
+
+/// Import `source` into `history_file`. Returns (added, duplicates skipped).
+pub fn import(history_file: &Path, source: &Path) -> Result<(usize, usize), String> {
+    let content = fs::read_to_string(source)
+        .map_err(|e| format!("Error: Cannot read '{}': {}", source.display(), e))?;
+    let incoming = parse_import(&content)?;
+    let incoming_count = incoming.len();
+
+    let write_error = |e: std::io::Error| {
+        format!(
+            "Error: Cannot update history file '{}': {}",
+            history_file.display(),
+            e
+        )
+    };
+    let lock = open_locked(history_file).map_err(write_error)?;
+    let existing = read_locked(&lock).map_err(write_error)?;
+    let existing_lines: Vec<&str> = existing.lines().skip(1).collect();
+
+    let (merged, added) = merge(&existing_lines, incoming);
+    if added > 0 {
+        rewrite_rows(&lock, &merged).map_err(write_error)?;
+    }
+
+    Ok((added, incoming_count - added))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_record() -> Record {
+        Record::parse(
+            "2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,y:50%,a;b,98.5,a=1/2@90",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn split_csv_fields_handles_quotes() {
+        assert_eq!(
+            split_csv_fields(r#"a,"b,c","say ""hi""",d"#),
+            vec!["a", "b,c", r#"say "hi""#, "d"]
+        );
+        assert_eq!(split_csv_fields("a,,b"), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn csv_export_roundtrips_through_import() {
+        let mut record = sample_record();
+        record.missed_words = vec!["hello,".to_string(), "world".to_string()];
+        record.note = "tired, late".to_string();
+        let exported = export(&[record.clone()], ExportFormat::Csv).unwrap();
+
+        let imported = parse_import(&exported).unwrap();
+        assert_eq!(imported, vec![record]);
+    }
+
+    #[test]
+    fn quoted_commas_are_escaped_in_native_rows() {
+        let content = format!(
+            "{}\n2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,\",:50%\",\"hello,;world\",,,,,\n",
+            CSV_HEADER
+        );
+        let imported = parse_import(&content).unwrap();
+        assert_eq!(imported[0].worst_keys, vec![",:50%"]);
+        assert_eq!(imported[0].missed_words, vec!["hello,", "world"]);
+
+        let line = imported[0].to_csv_line();
+        assert_eq!(line.split(',').count(), crate::history::CSV_FIELDS);
+        assert_eq!(Record::parse(&line).unwrap(), imported[0]);
+    }
+
+    #[test]
+    fn json_export_roundtrips_through_import() {
+        let record = sample_record();
+        let exported = export(std::slice::from_ref(&record), ExportFormat::Json).unwrap();
+        assert_eq!(parse_import(&exported).unwrap(), vec![record]);
+    }
+
+    #[test]
+    fn imports_native_history_file() {
+        let content = format!(
+            "{}\n2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,a:90%,\n",
+            CSV_HEADER
+        );
+        let records = parse_import(&content).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].language, "english");
+    }
+
+    #[test]
+    fn imports_monkeytype_csv() {
+        let content = "_id,isPb,wpm,acc,rawWpm,consistency,charStats,mode,mode2,quoteLength,restartCount,testDuration,afkDuration,incompleteTestSeconds,lazyMode,blindMode,bailedOut,tags,timestamp,language,funbox,difficulty,numbers,punctuation\n\
+            abc,true,85.5,97.2,88.1,80,\"190;4;1;2\",words,50,-1,0,30.1,0,0,false,false,false,,1771063200000,english,none,normal,false,false\n\
+            def,false,60,90,70,70,190;10;0;0,time,30,-1,0,30,0,0,false,false,false,,1771066800000,german,none,normal,false,false";
+        let records = parse_import(content).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].words, 50);
+        assert!((records[0].wpm_adjusted - 85.5).abs() < 0.01);
+        assert!((records[0].wpm_raw - 88.1).abs() < 0.01);
+        assert_eq!(records[0].correct, 190);
+        assert_eq!(records[0].total, 197);
+        assert_eq!(records[1].words, 0, "time mode has no word count");
+        assert_eq!(records[1].language, "german");
+    }
+
+    #[test]
+    fn imports_monkeytype_json() {
+        let content = r#"[{"wpm": 85.5, "rawWpm": 88.1, "acc": 97.2, "timestamp": 1771063200000,
+            "mode": "words", "mode2": "25", "charStats": [100, 2, 0, 1]}]"#;
+        let records = parse_import(content).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].words, 25);
+        assert_eq!(records[0].language, "english");
+        assert_eq!(records[0].total, 103);
+    }
+
+    #[test]
+    fn monkeytype_results_are_identified_by_timestamp() {
+        let content = r#"[{"wpm": 85.5, "rawWpm": 88.1, "acc": 97.2, "timestamp": 1771063200000}]"#;
+        let mut record = parse_import(content).unwrap().remove(0);
+        let identity = record.identity();
+        // As if imported again in another time zone
+        record.datetime = "2026-02-13 23:00:00".to_string();
+        assert_eq!(record.identity(), identity);
+    }
+
+    #[test]
+    fn imports_list_columns_from_older_json_exports() {
+        let content = r#"[{"datetime": "2026-02-14 10:00:00", "language": "english", "words": 50,
+            "wpm_raw": 82.0, "wpm_adjusted": 77.9, "accuracy": 95.0, "correct": 380, "total": 400,
+            "worst_keys": "y:50%", "missed_words": "a;b"}]"#;
+        let records = parse_import(content).unwrap();
+        assert_eq!(records[0].worst_keys, vec!["y:50%"]);
+        assert_eq!(records[0].missed_words, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(parse_import("foo,bar\n1,2").is_err());
+        assert!(parse_import(r#"[{"foo": 1}]"#).is_err());
+    }
+
+    #[test]
+    fn merge_skips_duplicates_and_sorts() {
+        let existing = vec![
+            "2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,a:90%,",
+            "2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,y:50%,a;b,98.5,a=1/2@90",
+        ];
+        let mut earlier = sample_record();
+        earlier.datetime = "2026-02-12 10:00:00".to_string();
+        let (merged, added) = merge(&existing, vec![sample_record(), earlier]);
+
+        assert_eq!(added, 1);
+        assert_eq!(merged.len(), 3);
+        assert!(merged[1].starts_with("2026-02-12"));
+    }
+
+    #[test]
+    fn import_merges_into_file() {
+        let dir = std::env::temp_dir().join("ttyper_test_import");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let history = dir.join("history.csv");
+        let source = dir.join("export.json");
+        fs::write(
+            &source,
+            export(&[sample_record()], ExportFormat::Json).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(import(&history, &source).unwrap(), (1, 0));
+        assert_eq!(import(&history, &source).unwrap(), (0, 1));
+
+        let content = fs::read_to_string(&history).unwrap();
+        assert_eq!(content.lines().next(), Some(CSV_HEADER));
+        assert_eq!(content.lines().count(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
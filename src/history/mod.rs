@@ -0,0 +1,2138 @@
+pub mod edit;
+pub mod interop;
+pub mod record;
+pub mod verify;
+
+use crate::test::results::{Fraction, Results};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use fs4::FileExt;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const WPM_PER_CPS: f64 = 12.0;
+pub const CSV_HEADER: &str =
+    "datetime,language,words,wpm_raw,wpm_adjusted,accuracy,correct,total,worst_keys,missed_words,avg_dwell_ms,key_stats,tags,note,options";
+/// Number of columns in a current-format row. Older rows may have fewer.
+pub const CSV_FIELDS: usize = 15;
+/// Number of most recent sessions per key used for the "recent" column of `--keys`.
+const RECENT_KEY_SESSIONS: usize = 10;
+
+/// Calculate raw and adjusted WPM from characters per second and accuracy (0.0–1.0).
+pub fn calculate_wpms(cps: f64, accuracy: f64) -> (f64, f64) {
+    let raw = cps * WPM_PER_CPS;
+    let adjusted = raw * accuracy;
+    (raw, adjusted)
+}
+
+/// Format worst keys from per-key accuracy data.
+/// Returns semicolon-separated string of up to 5 worst keys, sorted by accuracy ascending.
+/// Keys at 100% accuracy are excluded. Format: "y:50%;A:75%;c:81%", with keys escaped.
+pub fn format_worst_keys(per_key: &HashMap<KeyEvent, Fraction>) -> String {
+    let mut worst_keys: Vec<_> = per_key
+        .iter()
+        .filter(|(key, _)| matches!(key.code, KeyCode::Char(_)))
+        .map(|(key, frac)| {
+            let ch = if let KeyCode::Char(c) = key.code {
+                c
+            } else {
+                '?'
+            };
+            (ch, f64::from(*frac) * 100.0)
+        })
+        .filter(|(_, acc)| *acc < 100.0)
+        .collect();
+    worst_keys.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+    let entries: Vec<String> = worst_keys
+        .iter()
+        .take(5)
+        .map(|(ch, acc)| format!("{}:{:.0}%", ch, acc))
+        .collect();
+    format_worst_key_list(&entries)
+}
+
+/// Escape characters that would break the `key_stats` column (CSV and field separators).
+fn escape_key(c: char) -> String {
+    match c {
+        ',' | ';' | '=' | '@' | '%' => format!("%{:02X}", c as u32),
+        c if c.is_whitespace() || c.is_control() => format!("%{:02X}", c as u32),
+        c => c.to_string(),
+    }
+}
+
+/// Reverse of [`escape_key`]. Returns None for malformed input.
+fn unescape_key(s: &str) -> Option<char> {
+    match s.strip_prefix('%') {
+        Some(hex) => char::from_u32(u32::from_str_radix(hex, 16).ok()?),
+        None => {
+            let mut chars = s.chars();
+            let c = chars.next()?;
+            chars.next().is_none().then_some(c)
+        }
+    }
+}
+
+/// Escape free text (tags and notes) so it fits in a single field of a history row.
+pub fn escape_text(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            ',' | ';' | '%' => format!("%{:02X}", c as u32),
+            c if c.is_control() => format!("%{:02X}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Reverse of [`escape_text`]. Malformed escapes are kept as-is.
+pub fn unescape_text(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('%') {
+        out.push_str(&rest[..i]);
+        let decoded = rest
+            .get(i + 1..i + 3)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32);
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[i + 3..];
+            }
+            None => {
+                out.push('%');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Format a list column (tags, missed words): escaped items separated by `;`.
+pub fn format_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| escape_text(item))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Parse a list column (tags, missed words).
+pub fn parse_list(field: &str) -> Vec<String> {
+    field
+        .split(';')
+        .filter(|item| !item.is_empty())
+        .map(unescape_text)
+        .collect()
+}
+
+/// Format the `worst_keys` column from `key:accuracy` entries, escaping the keys.
+pub fn format_worst_key_list(entries: &[String]) -> String {
+    entries
+        .iter()
+        .map(|entry| match entry.rsplit_once(':') {
+            Some((key, accuracy)) => format!("{}:{}", escape_text(key), accuracy),
+            None => escape_text(entry),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Parse the `worst_keys` column into `key:accuracy` entries.
+pub fn parse_worst_key_list(field: &str) -> Vec<String> {
+    parse_list(field)
+}
+
+/// Value of `key` in the `options` column, still escaped.
+pub fn option_value<'a>(options: &'a str, key: &str) -> Option<&'a str> {
+    options
+        .split(';')
+        .find_map(|option| option.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// Lifetime-aggregatable statistics for a single key in a single session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyStat {
+    pub correct: usize,
+    pub total: usize,
+    /// Average time to reach this key from the previous keypress, in milliseconds.
+    pub avg_ms: Option<f64>,
+}
+
+/// Collect per-character statistics from results, merging modifier variants of the same char.
+fn collect_key_stats(results: &Results) -> Vec<(char, KeyStat)> {
+    let mut stats: HashMap<char, KeyStat> = HashMap::new();
+    for (key, frac) in &results.accuracy.per_key {
+        if let KeyCode::Char(c) = key.code {
+            let stat = stats.entry(c).or_insert(KeyStat {
+                correct: 0,
+                total: 0,
+                avg_ms: None,
+            });
+            stat.correct += frac.numerator;
+            stat.total += frac.denominator;
+        }
+    }
+
+    // Timing is weighted by keypress count so modifier variants merge correctly
+    let mut timing: HashMap<char, (f64, usize)> = HashMap::new();
+    for (key, secs) in &results.timing.per_key {
+        if let KeyCode::Char(c) = key.code {
+            let weight = results
+                .accuracy
+                .per_key
+                .get(key)
+                .map_or(1, |f| f.denominator.max(1));
+            let entry = timing.entry(c).or_insert((0.0, 0));
+            entry.0 += secs * 1000.0 * weight as f64;
+            entry.1 += weight;
+        }
+    }
+    for (c, (sum, weight)) in timing {
+        if let Some(stat) = stats.get_mut(&c) {
+            stat.avg_ms = Some(sum / weight as f64);
+        }
+    }
+
+    let mut stats: Vec<(char, KeyStat)> = stats.into_iter().collect();
+    stats.sort_by_key(|(c, _)| *c);
+    stats
+}
+
+/// Format full per-key statistics for the `key_stats` column.
+/// Format: "a=9/10@123;b=4/4@98" (correct/total@avg ms). Timing is omitted when unknown.
+pub fn format_key_stats(results: &Results) -> String {
+    collect_key_stats(results)
+        .iter()
+        .map(|(c, stat)| {
+            let timing = stat
+                .avg_ms
+                .map_or(String::new(), |ms| format!("@{:.0}", ms));
+            format!(
+                "{}={}/{}{}",
+                escape_key(*c),
+                stat.correct,
+                stat.total,
+                timing
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Parse the `key_stats` column. Malformed entries are skipped.
+pub fn parse_key_stats(field: &str) -> Vec<(char, KeyStat)> {
+    field
+        .split_terminator(';')
+        .filter_map(|entry| {
+            let (key, rest) = entry.split_once('=')?;
+            let (counts, avg_ms) = match rest.split_once('@') {
+                Some((counts, ms)) => (counts, Some(ms.parse().ok()?)),
+                None => (rest, None),
+            };
+            let (correct, total) = counts.split_once('/')?;
+            Some((
+                unescape_key(key)?,
+                KeyStat {
+                    correct: correct.parse().ok()?,
+                    total: total.parse().ok()?,
+                    avg_ms,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Format a single CSV data line. Timestamp is passed in to keep the function pure/testable.
+/// `options` records how the words were chosen, as `;`-separated `key=value` pairs.
+pub fn format_csv_line(
+    timestamp: &str,
+    language: &str,
+    words: usize,
+    options: &str,
+    results: &Results,
+) -> String {
+    let accuracy = f64::from(results.accuracy.overall);
+    let (raw_wpm, adjusted_wpm) = calculate_wpms(results.timing.overall_cps, accuracy);
+    let worst_str = format_worst_keys(&results.accuracy.per_key);
+    let missed_str = format_list(&results.missed_words);
+
+    let dwell_str = results
+        .dwell
+        .overall_avg_ms
+        .map_or(String::new(), |ms| format!("{:.1}", ms));
+    let key_stats_str = format_key_stats(results);
+
+    // New results have no tags or note yet
+    format!(
+        "{},{},{},{:.1},{:.1},{:.1},{},{},{},{},{},{},,,{}",
+        timestamp,
+        escape_text(language),
+        words,
+        raw_wpm,
+        adjusted_wpm,
+        accuracy * 100.0,
+        results.accuracy.overall.numerator,
+        results.accuracy.overall.denominator,
+        worst_str,
+        missed_str,
+        dwell_str,
+        key_stats_str,
+        options,
+    )
+}
+
+/// Exclusive advisory lock on a history file, released when dropped.
+/// The lock is held on a separate `.lock` file next to the history file, because
+/// rewrites replace the history file itself and waiters must not be left locking
+/// the replaced inode.
+pub struct HistoryLock {
+    _lock: fs::File,
+    path: PathBuf,
+}
+
+/// Path of the lock file guarding `history_file`.
+fn lock_path(history_file: &Path) -> PathBuf {
+    let mut name = history_file.as_os_str().to_os_string();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+fn open_lock_file(history_file: &Path) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(history_file))
+}
+
+/// Take an exclusive advisory lock on the history file.
+pub fn open_locked(history_file: &Path) -> io::Result<HistoryLock> {
+    let lock = open_lock_file(history_file)?;
+    lock.lock_exclusive()?;
+    Ok(HistoryLock {
+        _lock: lock,
+        path: history_file.to_path_buf(),
+    })
+}
+
+/// Read a history file while holding a shared lock, so a concurrent rewrite or
+/// append is never seen half done.
+pub fn read_shared(history_file: &Path) -> io::Result<String> {
+    let lock = open_lock_file(history_file)?;
+    FileExt::lock_shared(&lock)?;
+    fs::read_to_string(history_file)
+}
+
+/// Read the whole content of a locked history file. A missing file is empty.
+pub fn read_locked(lock: &HistoryLock) -> io::Result<String> {
+    match fs::read_to_string(&lock.path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+/// Replace the content of a locked history file atomically: the new content is
+/// written to a temp file in the same directory, synced and renamed over it.
+pub fn rewrite_locked(lock: &HistoryLock, content: &str) -> io::Result<()> {
+    let mut name = lock.path.as_os_str().to_os_string();
+    name.push(".tmp");
+    let tmp = PathBuf::from(name);
+
+    let mut file = fs::File::create(&tmp)?;
+    let written = file
+        .write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&tmp, &lock.path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// Replace the content of a locked history file with the current header and `lines`.
+pub fn rewrite_rows(lock: &HistoryLock, lines: &[String]) -> io::Result<()> {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    rewrite_locked(lock, &out)
+}
+
+/// Save results to history CSV file. Creates header if file is new, appends data line.
+/// The file is locked while writing and header and row are appended in a single write,
+/// so concurrent instances can neither duplicate the header nor interleave rows.
+/// Returns false without saving if `options` mark a daily challenge that already has
+/// a row, since only the first attempt counts.
+pub fn save_results(
+    history_file: &Path,
+    language: &str,
+    words: usize,
+    options: &str,
+    results: &Results,
+) -> io::Result<bool> {
+    let lock = open_locked(history_file)?;
+
+    // Checked under the lock, so two attempts finishing together can't both count
+    if let Some(date) = option_value(options, "daily") {
+        let content = read_locked(&lock)?;
+        let attempted = content
+            .lines()
+            .skip(1)
+            .filter_map(|line| record::Record::parse(line).ok())
+            .any(|r| option_value(&r.options, "daily") == Some(date));
+        if attempted {
+            return Ok(false);
+        }
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_file)?;
+
+    // Checked under the lock: another instance may have created the file meanwhile
+    let mut out = String::new();
+    if file.metadata()?.len() == 0 {
+        out.push_str(CSV_HEADER);
+        out.push('\n');
+    }
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    out.push_str(&format_csv_line(
+        &timestamp, language, words, options, results,
+    ));
+    out.push('\n');
+
+    file.write_all(out.as_bytes())?;
+    Ok(true)
+}
+
+/// Filtering options for history display.
+pub struct Filters<'a> {
+    pub language: Option<&'a str>,
+    pub since: Option<&'a str>,
+    pub until: Option<&'a str>,
+    pub tag: Option<&'a str>,
+}
+
+impl Filters<'_> {
+    /// Whether any filter is set.
+    pub fn is_active(&self) -> bool {
+        self.language.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.tag.is_some()
+    }
+}
+
+/// Check if a CSV line matches the given filters.
+/// Fields: datetime(0), language(1), ..., tags(12)
+fn matches_filters(fields: &[&str], filters: &Filters) -> bool {
+    if let Some(lang) = filters.language {
+        if fields.get(1).map(|l| unescape_text(l)).as_deref() != Some(lang) {
+            return false;
+        }
+    }
+    // A datetime that doesn't start with a date matches no date range
+    let date = fields.first().and_then(|datetime| datetime.get(..10));
+    if let Some(since) = filters.since {
+        match date {
+            Some(date) if date >= since => {}
+            _ => return false,
+        }
+    }
+    if let Some(until) = filters.until {
+        match date {
+            Some(date) if date <= until => {}
+            _ => return false,
+        }
+    }
+    if let Some(tag) = filters.tag {
+        if !fields
+            .get(12)
+            .is_some_and(|f| parse_list(f).iter().any(|t| t == tag))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// Validate date format (YYYY-MM-DD).
+pub fn validate_date_format(date: &str) -> Result<(), String> {
+    if date.len() != 10
+        || date.as_bytes()[4] != b'-'
+        || date.as_bytes()[7] != b'-'
+        || !date[..4].chars().all(|c| c.is_ascii_digit())
+        || !date[5..7].chars().all(|c| c.is_ascii_digit())
+        || !date[8..10].chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(format!(
+            "Error: Invalid date format '{}'. Expected YYYY-MM-DD (e.g., 2026-02-14).",
+            date
+        ));
+    }
+    Ok(())
+}
+
+/// Format history data rows into displayable lines, paired with their entry IDs.
+/// An entry's ID is its 1-based position among the data rows of the history file.
+/// Applies filters first, then `last` limits output to the most recent N entries.
+fn format_history_rows(
+    data_lines: &[&str],
+    last: Option<usize>,
+    filters: &Filters,
+) -> Vec<(usize, String)> {
+    let filtered: Vec<(usize, &str)> = data_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| {
+            let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+            fields.len() >= 9 && matches_filters(&fields, filters)
+        })
+        .map(|(i, line)| (i + 1, *line))
+        .collect();
+
+    let skip = match last {
+        Some(n) if n < filtered.len() => filtered.len() - n,
+        _ => 0,
+    };
+
+    filtered
+        .iter()
+        .skip(skip)
+        .map(|(id, line)| {
+            let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+            let mut row = format!(
+                "{:<20} {:<15} {:>5} {:>8} {:>8} {:>8} {}",
+                fields[0],
+                unescape_text(fields[1]),
+                fields[2],
+                fields[3],
+                fields[4],
+                fields[5],
+                parse_worst_key_list(fields[8]).join(";")
+            );
+            if let Some(options) = fields.get(14).filter(|o| !o.is_empty()) {
+                row.push_str(&format!(" [{}]", options));
+            }
+            for tag in fields.get(12).map_or(Vec::new(), |f| parse_list(f)) {
+                row.push_str(&format!(" #{}", tag));
+            }
+            if let Some(note) = fields.get(13).filter(|n| !n.is_empty()) {
+                row.push_str(&format!(" \"{}\"", unescape_text(note)));
+            }
+            (*id, row)
+        })
+        .collect()
+}
+
+/// Display history from CSV file in a formatted table.
+/// `last` limits output to the most recent N entries. None means show all.
+/// `filters` narrows results by language and/or date range.
+pub fn show_history(history_file: &Path, last: Option<usize>, filters: &Filters) {
+    if !history_file.exists() {
+        println!("No history found at {}", history_file.display());
+        return;
+    }
+
+    let content = read_shared(history_file).expect("Failed to read history file");
+    let lines: Vec<&str> = content.lines().collect();
+
+    if lines.len() <= 1 {
+        println!("No results recorded yet.");
+        return;
+    }
+
+    let data_lines = &lines[1..];
+    let rows = format_history_rows(data_lines, last, filters);
+    let shown = rows.len();
+    let total = data_lines.len();
+
+    let has_filters = last.is_some() || filters.is_active();
+
+    if shown == 0 && has_filters {
+        println!("No matching results for the given filters.");
+        return;
+    }
+
+    println!(
+        "{:>4} {:<20} {:<15} {:>5} {:>8} {:>8} {:>8} Worst Keys",
+        "ID", "Date", "Language", "Words", "Raw WPM", "Adj WPM", "Acc %"
+    );
+    println!("{}", "-".repeat(95));
+
+    for (id, row) in &rows {
+        println!("{:>4} {}", id, row);
+    }
+
+    if has_filters {
+        println!(
+            "\nShowing {} of {} results. History file: {}",
+            shown,
+            total,
+            history_file.display()
+        );
+    } else {
+        println!(
+            "\n{} results total. History file: {}",
+            total,
+            history_file.display()
+        );
+    }
+}
+
+/// A parsed history row for stats computation.
+struct HistoryRow {
+    date: String,
+    language: String,
+    words: usize,
+    wpm_raw: f64,
+    wpm_adj: f64,
+    accuracy: f64,
+    avg_dwell_ms: Option<f64>,
+    key_stats: Vec<(char, KeyStat)>,
+    /// Date of the daily challenge this was an attempt at
+    daily: Option<String>,
+}
+
+/// Parse filtered CSV data lines into HistoryRow structs.
+/// Also returns the number of rows that matched the filters but couldn't be parsed.
+fn parse_history_rows(data_lines: &[&str], filters: &Filters) -> (Vec<HistoryRow>, usize) {
+    let mut skipped = 0;
+    let rows = data_lines
+        .iter()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+            if !matches_filters(&fields, filters) {
+                return None;
+            }
+            let row = parse_history_row(&fields);
+            if row.is_none() {
+                skipped += 1;
+            }
+            row
+        })
+        .collect();
+    (rows, skipped)
+}
+
+/// Parse the fields of one CSV data line. Returns None for malformed rows.
+fn parse_history_row(fields: &[&str]) -> Option<HistoryRow> {
+    if fields.len() < 9 {
+        return None;
+    }
+    Some(HistoryRow {
+        date: fields[0].get(..10)?.to_string(),
+        language: unescape_text(fields[1]),
+        words: fields[2].parse().ok()?,
+        wpm_raw: fields[3].parse().ok()?,
+        wpm_adj: fields[4].parse().ok()?,
+        accuracy: fields[5].parse().ok()?,
+        avg_dwell_ms: fields.get(10).and_then(|s| s.parse().ok()),
+        key_stats: fields.get(11).map_or(Vec::new(), |s| parse_key_stats(s)),
+        daily: fields
+            .get(14)
+            .and_then(|options| option_value(options, "daily"))
+            .map(String::from),
+    })
+}
+
+/// Compute overall statistics from parsed rows.
+fn compute_overall_stats(rows: &[HistoryRow]) -> (f64, f64, f64, String, String, usize) {
+    if rows.is_empty() {
+        return (0.0, 0.0, 0.0, String::new(), String::new(), 0);
+    }
+
+    let avg_raw: f64 = rows.iter().map(|r| r.wpm_raw).sum::<f64>() / rows.len() as f64;
+    let avg_adj: f64 = rows.iter().map(|r| r.wpm_adj).sum::<f64>() / rows.len() as f64;
+    let avg_acc: f64 = rows.iter().map(|r| r.accuracy).sum::<f64>() / rows.len() as f64;
+    let first_date = rows.first().map(|r| r.date.clone()).unwrap_or_default();
+
+    // Most practiced language
+    let mut lang_counts: HashMap<&str, usize> = HashMap::new();
+    for row in rows {
+        *lang_counts.entry(&row.language).or_insert(0) += 1;
+    }
+    let (most_lang, most_count) = lang_counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .unwrap_or(("", 0));
+
+    (
+        avg_raw,
+        avg_adj,
+        avg_acc,
+        first_date,
+        most_lang.to_string(),
+        most_count,
+    )
+}
+
+/// Compute stats for rows within a date range (inclusive string comparison on YYYY-MM-DD).
+fn rows_in_range<'a>(rows: &'a [HistoryRow], since: &str, until: &str) -> Vec<&'a HistoryRow> {
+    rows.iter()
+        .filter(|r| r.date.as_str() >= since && r.date.as_str() <= until)
+        .collect()
+}
+
+/// Compute average adjusted WPM for a slice of rows.
+fn avg_wpm(rows: &[&HistoryRow]) -> f64 {
+    if rows.is_empty() {
+        return 0.0;
+    }
+    rows.iter().map(|r| r.wpm_adj).sum::<f64>() / rows.len() as f64
+}
+
+/// Compute average accuracy for a slice of rows.
+fn avg_accuracy(rows: &[&HistoryRow]) -> f64 {
+    if rows.is_empty() {
+        return 0.0;
+    }
+    rows.iter().map(|r| r.accuracy).sum::<f64>() / rows.len() as f64
+}
+
+/// Find best session (highest adjusted WPM) from a slice of rows.
+fn best_session<'a>(rows: &[&'a HistoryRow]) -> Option<(&'a str, f64)> {
+    rows.iter()
+        .max_by(|a, b| a.wpm_adj.partial_cmp(&b.wpm_adj).unwrap())
+        .map(|r| (r.date.as_str(), r.wpm_adj))
+}
+
+/// Compute weekly WPM averages. Returns (ISO week label, avg adjusted WPM) pairs.
+fn weekly_trend(rows: &[HistoryRow]) -> Vec<(String, f64)> {
+    use chrono::NaiveDate;
+
+    let mut week_data: HashMap<String, Vec<f64>> = HashMap::new();
+    for row in rows {
+        if let Ok(date) = NaiveDate::parse_from_str(&row.date, "%Y-%m-%d") {
+            let week = date.format("W%V").to_string();
+            week_data.entry(week).or_default().push(row.wpm_adj);
+        }
+    }
+
+    let mut weeks: Vec<(String, f64)> = week_data
+        .into_iter()
+        .map(|(week, wpms)| {
+            let avg = wpms.iter().sum::<f64>() / wpms.len() as f64;
+            (week, avg)
+        })
+        .collect();
+    weeks.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Show last 6 weeks max
+    if weeks.len() > 6 {
+        weeks = weeks.split_off(weeks.len() - 6);
+    }
+    weeks
+}
+
+/// Grouping key for the `--group-by` comparison table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    Language,
+    Words,
+    Week,
+    Month,
+}
+
+/// Aggregated statistics for a single group of history rows.
+struct GroupStats {
+    key: String,
+    count: usize,
+    avg_wpm: f64,
+    best_wpm: f64,
+    avg_acc: f64,
+    /// Avg adjusted WPM of the newer half minus the older half. None with fewer than 2 tests.
+    trend: Option<f64>,
+}
+
+/// Compute the group label of a row. Weeks use ISO week-years so labels sort chronologically.
+fn group_key(row: &HistoryRow, group_by: GroupBy) -> String {
+    use chrono::NaiveDate;
+
+    match group_by {
+        GroupBy::Language => row.language.clone(),
+        GroupBy::Words => row.words.to_string(),
+        GroupBy::Week => NaiveDate::parse_from_str(&row.date, "%Y-%m-%d")
+            .map(|d| d.format("%G-W%V").to_string())
+            .unwrap_or_default(),
+        GroupBy::Month => row.date.get(..7).unwrap_or_default().to_string(),
+    }
+}
+
+/// Aggregate rows into per-group statistics.
+/// Languages are sorted by test count (most practiced first), word counts numerically,
+/// weeks and months chronologically.
+fn compute_group_stats(rows: &[HistoryRow], group_by: GroupBy) -> Vec<GroupStats> {
+    let mut groups: HashMap<String, Vec<&HistoryRow>> = HashMap::new();
+    for row in rows {
+        groups
+            .entry(group_key(row, group_by))
+            .or_default()
+            .push(row);
+    }
+
+    let mut stats: Vec<GroupStats> = groups
+        .into_iter()
+        .map(|(key, rows)| {
+            let trend = if rows.len() >= 2 {
+                let (older, newer) = rows.split_at(rows.len() / 2);
+                Some(avg_wpm(newer) - avg_wpm(older))
+            } else {
+                None
+            };
+            GroupStats {
+                key,
+                count: rows.len(),
+                avg_wpm: avg_wpm(&rows),
+                best_wpm: best_session(&rows).map_or(0.0, |(_, wpm)| wpm),
+                avg_acc: avg_accuracy(&rows),
+                trend,
+            }
+        })
+        .collect();
+
+    match group_by {
+        GroupBy::Language => {
+            stats.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)))
+        }
+        GroupBy::Words => stats.sort_by_key(|g| g.key.parse::<usize>().unwrap_or(0)),
+        GroupBy::Week | GroupBy::Month => stats.sort_by(|a, b| a.key.cmp(&b.key)),
+    }
+    stats
+}
+
+/// Format a trend delta with sign and arrow, e.g. "+3.2 ^". Dash when there is no trend.
+fn format_trend(trend: Option<f64>) -> String {
+    match trend {
+        Some(delta) if delta > 0.0 => format!("+{:.1} ^", delta),
+        Some(delta) if delta < 0.0 => format!("{:.1} v", delta),
+        Some(_) => "0.0".to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Print a side-by-side comparison table of rows grouped by `group_by`.
+fn print_group_table(rows: &[HistoryRow], group_by: GroupBy) {
+    let label = match group_by {
+        GroupBy::Language => "Language",
+        GroupBy::Words => "Words",
+        GroupBy::Week => "Week",
+        GroupBy::Month => "Month",
+    };
+
+    println!(
+        "{:<15} {:>6} {:>8} {:>9} {:>8} {:>9}",
+        label, "Tests", "Avg WPM", "Best WPM", "Acc %", "Trend"
+    );
+    println!("{}", "-".repeat(60));
+
+    for group in compute_group_stats(rows, group_by) {
+        println!(
+            "{:<15} {:>6} {:>8.1} {:>9.1} {:>8.1} {:>9}",
+            group.key,
+            group.count,
+            group.avg_wpm,
+            group.best_wpm,
+            group.avg_acc,
+            format_trend(group.trend)
+        );
+    }
+}
+
+/// Lifetime statistics for one key, aggregated across sessions.
+struct KeyReport {
+    key: char,
+    correct: usize,
+    total: usize,
+    avg_ms: Option<f64>,
+    /// Accuracy (%) over the most recent sessions containing this key.
+    recent_acc: f64,
+    /// Accuracy (%) over all older sessions. None when every session counts as recent.
+    earlier_acc: Option<f64>,
+}
+
+/// Accuracy percentage of summed key stats.
+fn key_accuracy(stats: &[&KeyStat]) -> f64 {
+    let correct: usize = stats.iter().map(|s| s.correct).sum();
+    let total: usize = stats.iter().map(|s| s.total).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    correct as f64 / total as f64 * 100.0
+}
+
+/// Aggregate per-key statistics across all rows. Rows are expected in chronological order.
+/// Sorted by lifetime accuracy ascending (weakest keys first).
+fn compute_key_reports(rows: &[HistoryRow]) -> Vec<KeyReport> {
+    let mut sessions: HashMap<char, Vec<&KeyStat>> = HashMap::new();
+    for row in rows {
+        for (c, stat) in &row.key_stats {
+            sessions.entry(*c).or_default().push(stat);
+        }
+    }
+
+    let mut reports: Vec<KeyReport> = sessions
+        .into_iter()
+        .map(|(key, stats)| {
+            let timed: Vec<(f64, usize)> = stats
+                .iter()
+                .filter_map(|s| s.avg_ms.map(|ms| (ms, s.total.max(1))))
+                .collect();
+            let avg_ms = if timed.is_empty() {
+                None
+            } else {
+                let weight: usize = timed.iter().map(|(_, w)| w).sum();
+                Some(timed.iter().map(|(ms, w)| ms * *w as f64).sum::<f64>() / weight as f64)
+            };
+
+            let split = stats.len().saturating_sub(RECENT_KEY_SESSIONS);
+            let (earlier, recent) = stats.split_at(split);
+
+            KeyReport {
+                key,
+                correct: stats.iter().map(|s| s.correct).sum(),
+                total: stats.iter().map(|s| s.total).sum(),
+                avg_ms,
+                recent_acc: key_accuracy(recent),
+                earlier_acc: (!earlier.is_empty()).then(|| key_accuracy(earlier)),
+            }
+        })
+        .filter(|r| r.total > 0)
+        .collect();
+
+    reports.sort_by(|a, b| {
+        let acc_a = a.correct as f64 / a.total as f64;
+        let acc_b = b.correct as f64 / b.total as f64;
+        acc_a
+            .partial_cmp(&acc_b)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    reports
+}
+
+/// Print the lifetime per-key report.
+fn print_key_report(rows: &[HistoryRow]) {
+    let reports = compute_key_reports(rows);
+    if reports.is_empty() {
+        println!("No per-key data recorded yet. Per-key stats are saved for new tests only.");
+        return;
+    }
+
+    println!(
+        "{:<5} {:>7} {:>8} {:>8} {:>9} {:>9}",
+        "Key", "Presses", "Acc %", "Avg ms", "Recent %", "Change"
+    );
+    println!("{}", "-".repeat(52));
+
+    for report in &reports {
+        let label = match report.key {
+            ' ' => "Space".to_string(),
+            c => c.to_string(),
+        };
+        let avg_ms = report
+            .avg_ms
+            .map_or("-".to_string(), |ms| format!("{:.0}", ms));
+        let change = format_trend(report.earlier_acc.map(|e| report.recent_acc - e));
+        println!(
+            "{:<5} {:>7} {:>8.1} {:>8} {:>9.1} {:>9}",
+            label,
+            report.total,
+            report.correct as f64 / report.total as f64 * 100.0,
+            avg_ms,
+            report.recent_acc,
+            change
+        );
+    }
+
+    println!(
+        "\nRecent = last {} sessions containing the key; Change = recent vs. earlier accuracy.",
+        RECENT_KEY_SESSIONS
+    );
+}
+
+/// Number of recent daily challenges listed by `--daily`.
+const RECENT_DAILY_CHALLENGES: usize = 10;
+
+/// Each daily challenge's date and scored row, oldest first. Only the first attempt
+/// at a challenge counts.
+fn daily_scores(rows: &[HistoryRow]) -> Vec<(chrono::NaiveDate, &HistoryRow)> {
+    let mut scores: Vec<(chrono::NaiveDate, &HistoryRow)> = Vec::new();
+    for row in rows {
+        let Some(date) = row.daily.as_deref().and_then(|d| d.parse().ok()) else {
+            continue;
+        };
+        if scores.iter().all(|(d, _)| *d != date) {
+            scores.push((date, row));
+        }
+    }
+    scores.sort_by_key(|(date, _)| *date);
+    scores
+}
+
+/// Current and longest runs of daily challenges on consecutive days, given the
+/// challenge dates in order. The current streak is still alive if the last challenge
+/// was today or yesterday.
+fn daily_streaks(dates: &[chrono::NaiveDate], today: chrono::NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<chrono::NaiveDate> = None;
+    for &date in dates {
+        run = match previous {
+            Some(p) if date - p == chrono::Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(date);
+    }
+    let current = match previous {
+        Some(last) if today - last <= chrono::Duration::days(1) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+/// Print the daily challenge streak and recent scores.
+fn print_daily_report(rows: &[HistoryRow]) {
+    let scores = daily_scores(rows);
+    let Some((first, _)) = scores.first() else {
+        println!("No daily challenges recorded yet. Take one with `ttyper --daily`.");
+        return;
+    };
+
+    let dates: Vec<chrono::NaiveDate> = scores.iter().map(|(date, _)| *date).collect();
+    let (current, longest) = daily_streaks(&dates, chrono::Local::now().date_naive());
+    println!("Daily challenge ({} days, since {})", scores.len(), first);
+    println!("  Current streak: {} days", current);
+    println!("  Longest streak: {} days", longest);
+    if let Some((date, best)) = scores
+        .iter()
+        .max_by(|a, b| a.1.wpm_adj.total_cmp(&b.1.wpm_adj))
+    {
+        println!("  Best: {:.1} WPM on {}", best.wpm_adj, date);
+    }
+
+    let recent = &scores[scores.len().saturating_sub(RECENT_DAILY_CHALLENGES)..];
+    println!(
+        "\n{:<10} {:>8} {:>8} {:>9}",
+        "Date", "Adj WPM", "Acc %", "Change"
+    );
+    println!("{}", "-".repeat(38));
+    let mut previous: Option<f64> = None;
+    for (date, row) in recent {
+        let change = format_trend(previous.map(|p| row.wpm_adj - p));
+        println!(
+            "{:<10} {:>8.1} {:>8.1} {:>9}",
+            date.to_string(),
+            row.wpm_adj,
+            row.accuracy,
+            change
+        );
+        previous = Some(row.wpm_adj);
+    }
+    if let [(_, first), .., (_, last)] = recent {
+        println!(
+            "\nTrend over the last {} challenges: {:+.1} WPM",
+            recent.len(),
+            last.wpm_adj - first.wpm_adj
+        );
+    }
+}
+
+/// Which statistics view to print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsView {
+    /// Overall summary, last 7 days and weekly trend.
+    Summary,
+    /// Side-by-side comparison table.
+    Grouped(GroupBy),
+    /// Lifetime per-key accuracy and speed.
+    Keys,
+    /// Daily challenge streak and scores.
+    Daily,
+}
+
+/// The rows `view` is computed from. Daily challenges are scored on their own by
+/// `--daily`, so they're left out of the other views.
+fn rows_for_view(mut rows: Vec<HistoryRow>, view: StatsView) -> Vec<HistoryRow> {
+    if view != StatsView::Daily {
+        rows.retain(|row| row.daily.is_none());
+    }
+    rows
+}
+
+/// Display aggregated statistics from history CSV file.
+pub fn show_stats(history_file: &Path, filters: &Filters, view: StatsView) {
+    if !history_file.exists() {
+        println!("No history found at {}", history_file.display());
+        return;
+    }
+
+    let content = read_shared(history_file).expect("Failed to read history file");
+    let lines: Vec<&str> = content.lines().collect();
+
+    if lines.len() <= 1 {
+        println!("No results recorded yet.");
+        return;
+    }
+
+    let data_lines = &lines[1..];
+    let (rows, skipped) = parse_history_rows(data_lines, filters);
+    if skipped > 0 {
+        eprintln!(
+            "Skipped {} malformed {} in {}. Run `ttyper history verify` for details.",
+            skipped,
+            if skipped == 1 { "row" } else { "rows" },
+            history_file.display()
+        );
+    }
+    let rows = rows_for_view(rows, view);
+
+    if rows.is_empty() {
+        println!("No matching results for the given filters.");
+        return;
+    }
+
+    match view {
+        StatsView::Summary => {}
+        StatsView::Grouped(group_by) => return print_group_table(&rows, group_by),
+        StatsView::Keys => return print_key_report(&rows),
+        StatsView::Daily => return print_daily_report(&rows),
+    }
+
+    let (avg_raw, avg_adj, avg_acc, first_date, most_lang, most_count) =
+        compute_overall_stats(&rows);
+
+    println!("Overall ({} tests, since {})", rows.len(), first_date);
+    println!("  Avg WPM: {:.1} (raw: {:.1})", avg_adj, avg_raw);
+    println!("  Avg Accuracy: {:.1}%", avg_acc);
+    println!("  Most practiced: {} ({} tests)", most_lang, most_count);
+
+    // Overall dwell stats (if any rows have dwell data)
+    let dwell_values: Vec<f64> = rows.iter().filter_map(|r| r.avg_dwell_ms).collect();
+    if !dwell_values.is_empty() {
+        let avg_dwell = dwell_values.iter().sum::<f64>() / dwell_values.len() as f64;
+        println!("  Avg Key Hold: {:.0}ms", avg_dwell);
+    }
+
+    // Last 7 days stats
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let seven_days_ago = (chrono::Local::now() - chrono::Duration::days(7))
+        .format("%Y-%m-%d")
+        .to_string();
+    let eight_days_ago = (chrono::Local::now() - chrono::Duration::days(8))
+        .format("%Y-%m-%d")
+        .to_string();
+    let fourteen_days_ago = (chrono::Local::now() - chrono::Duration::days(14))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let recent = rows_in_range(&rows, &seven_days_ago, &today);
+    if !recent.is_empty() {
+        let recent_wpm = avg_wpm(&recent);
+        let recent_acc = avg_accuracy(&recent);
+
+        println!("\nLast 7 days ({} tests)", recent.len());
+        // Delta vs prior week (exclusive upper bound to avoid double-counting)
+        let prior = rows_in_range(&rows, &fourteen_days_ago, &eight_days_ago);
+        if !prior.is_empty() {
+            let prior_wpm = avg_wpm(&prior);
+            let delta = recent_wpm - prior_wpm;
+            let sign = if delta >= 0.0 { "+" } else { "" };
+            println!(
+                "  Avg WPM: {:.1} ({}{:.1} vs prior week)",
+                recent_wpm, sign, delta
+            );
+        } else {
+            println!("  Avg WPM: {:.1}", recent_wpm);
+        }
+        println!("  Avg Accuracy: {:.1}%", recent_acc);
+        if let Some((date, wpm)) = best_session(&recent) {
+            println!("  Best session: {:.1} WPM on {}", wpm, date);
+        }
+    }
+
+    // Weekly trend
+    let weeks = weekly_trend(&rows);
+    if weeks.len() >= 2 {
+        let trend_arrow = if weeks.last().unwrap().1 > weeks[weeks.len() - 2].1 {
+            " ^"
+        } else if weeks.last().unwrap().1 < weeks[weeks.len() - 2].1 {
+            " v"
+        } else {
+            ""
+        };
+        println!("\nWeekly Trend (Adj WPM):");
+        let trend_str: String = weeks
+            .iter()
+            .map(|(week, wpm)| format!("  {}: {:.1}", week, wpm))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}{}", trend_str, trend_arrow);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::results::{AccuracyData, DwellData, TimingData};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::collections::HashMap;
+
+    fn make_key_event(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn make_results(
+        cps: f64,
+        correct: usize,
+        total: usize,
+        per_key: Vec<(char, usize, usize)>,
+        missed: Vec<&str>,
+    ) -> Results {
+        let mut key_accuracy = HashMap::new();
+        let mut key_timing = HashMap::new();
+        for (ch, num, den) in per_key {
+            let ke = make_key_event(ch);
+            key_accuracy.insert(ke, Fraction::new(num, den));
+            key_timing.insert(ke, 0.1);
+        }
+
+        Results {
+            save_error: None,
+            code: None,
+            timing: TimingData {
+                overall_cps: cps,
+                per_event: vec![],
+                per_key: key_timing,
+            },
+            accuracy: AccuracyData {
+                overall: Fraction::new(correct, total),
+                per_key: key_accuracy,
+            },
+            dwell: DwellData {
+                per_key: vec![],
+                overall_avg_ms: None,
+                has_data: false,
+            },
+            missed_words: missed.into_iter().map(String::from).collect(),
+            slow_words: vec![],
+            words: vec![],
+        }
+    }
+
+    // --- WPM calculation ---
+
+    #[test]
+    fn test_calculate_wpms() {
+        let (raw, adjusted) = calculate_wpms(5.0, 0.95);
+        assert!((raw - 60.0).abs() < 0.001);
+        assert!((adjusted - 57.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_calculate_wpms_perfect_accuracy() {
+        let (raw, adjusted) = calculate_wpms(7.0, 1.0);
+        assert!((raw - 84.0).abs() < 0.001);
+        assert!((raw - adjusted).abs() < 0.001);
+    }
+
+    // --- Worst keys formatting ---
+
+    #[test]
+    fn test_format_worst_keys_sorted_by_accuracy() {
+        let mut per_key = HashMap::new();
+        per_key.insert(make_key_event('a'), Fraction::new(9, 10)); // 90%
+        per_key.insert(make_key_event('b'), Fraction::new(1, 2)); // 50%
+        per_key.insert(make_key_event('c'), Fraction::new(3, 4)); // 75%
+
+        let result = format_worst_keys(&per_key);
+        assert_eq!(result, "b:50%;c:75%;a:90%");
+    }
+
+    #[test]
+    fn test_format_worst_keys_max_five() {
+        let mut per_key = HashMap::new();
+        for (i, ch) in "abcdefgh".chars().enumerate() {
+            per_key.insert(make_key_event(ch), Fraction::new(i, 10));
+        }
+
+        let result = format_worst_keys(&per_key);
+        assert_eq!(result.split(';').count(), 5);
+    }
+
+    #[test]
+    fn test_format_worst_keys_all_perfect() {
+        let mut per_key = HashMap::new();
+        per_key.insert(make_key_event('a'), Fraction::new(10, 10));
+        per_key.insert(make_key_event('b'), Fraction::new(5, 5));
+
+        let result = format_worst_keys(&per_key);
+        assert_eq!(result, "");
+    }
+
+    #[test]
+    fn test_format_worst_keys_preserves_case() {
+        let mut per_key = HashMap::new();
+        per_key.insert(make_key_event('A'), Fraction::new(1, 4)); // 25%
+        per_key.insert(make_key_event('a'), Fraction::new(9, 10)); // 90%
+
+        let result = format_worst_keys(&per_key);
+        assert!(result.contains("A:25%"));
+        assert!(result.contains("a:90%"));
+    }
+
+    #[test]
+    fn test_format_worst_keys_escapes_separators() {
+        let mut per_key = HashMap::new();
+        per_key.insert(make_key_event(','), Fraction::new(1, 2));
+        per_key.insert(make_key_event(';'), Fraction::new(3, 4));
+
+        let result = format_worst_keys(&per_key);
+        assert_eq!(result, "%2C:50%;%3B:75%");
+        assert_eq!(parse_worst_key_list(&result), vec![",:50%", ";:75%"]);
+    }
+
+    // --- CSV line formatting ---
+
+    #[test]
+    fn test_format_csv_line_field_order() {
+        let results = make_results(
+            6.5,
+            380,
+            400,
+            vec![('y', 1, 2)],
+            vec!["Architektur", "Frontend"],
+        );
+
+        let line = format_csv_line("2026-02-14 12:43:34", "peter1000", 50, "top=1000", &results);
+        let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+
+        assert_eq!(fields.len(), CSV_FIELDS);
+        assert_eq!(fields[0], "2026-02-14 12:43:34");
+        assert_eq!(fields[1], "peter1000");
+        assert_eq!(fields[2], "50");
+        assert_eq!(fields[6], "380");
+        assert_eq!(fields[7], "400");
+        assert_eq!(fields[9], "Architektur;Frontend");
+        assert_eq!(fields[10], "", "No dwell data → empty field");
+        assert_eq!(fields[11], "y=1/2@100");
+        assert_eq!(fields[12], "", "New results are untagged");
+        assert_eq!(fields[13], "");
+        assert_eq!(fields[14], "top=1000");
+    }
+
+    // --- Per-key stats ---
+
+    #[test]
+    fn test_format_key_stats_merges_modifier_variants() {
+        let mut results = make_results(5.0, 10, 12, vec![('a', 3, 4)], vec![]);
+        let shifted = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::SHIFT);
+        results
+            .accuracy
+            .per_key
+            .insert(shifted, Fraction::new(1, 4));
+        results.timing.per_key.insert(shifted, 0.3);
+
+        // counts summed; timing weighted by presses: (100 * 4 + 300 * 4) / 8 = 200
+        assert_eq!(format_key_stats(&results), "a=4/8@200");
+    }
+
+    #[test]
+    fn test_key_stats_escape_roundtrip() {
+        let results = make_results(5.0, 3, 4, vec![(',', 1, 2), (';', 2, 2)], vec![]);
+        let field = format_key_stats(&results);
+        assert!(!field.contains(','), "commas must be escaped: {}", field);
+
+        let parsed = parse_key_stats(&field);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].0, ',');
+        assert_eq!(parsed[0].1.total, 2);
+        assert_eq!(parsed[1].0, ';');
+        assert_eq!(parsed[1].1.correct, 2);
+    }
+
+    #[test]
+    fn test_parse_key_stats_without_timing_and_malformed() {
+        let parsed = parse_key_stats("a=1/2;garbage;b=3/3@90;c=x/2");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].1.avg_ms, None);
+        assert_eq!(parsed[1].1.avg_ms, Some(90.0));
+    }
+
+    #[test]
+    fn test_key_reports_lifetime_and_recent() {
+        let mut lines = vec![
+            "2026-02-01 10:00:00,english,50,72.0,68.4,95.0,190,200,,,,a=0/10@200;b=5/5@100"
+                .to_string(),
+        ];
+        for day in 2..=(RECENT_KEY_SESSIONS + 1) {
+            lines.push(format!(
+                "2026-02-{:02} 10:00:00,english,50,72.0,68.4,95.0,190,200,,,,a=10/10@100",
+                day
+            ));
+        }
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let reports = compute_key_reports(&rows);
+
+        // 'a' is weaker lifetime (100/110) than 'b' (5/5)
+        assert_eq!(reports[0].key, 'a');
+        assert_eq!(reports[0].correct, 100);
+        assert_eq!(reports[0].total, 110);
+        assert!((reports[0].recent_acc - 100.0).abs() < 0.01);
+        assert!((reports[0].earlier_acc.unwrap() - 0.0).abs() < 0.01);
+        // (200 * 10 + 100 * 100) / 110
+        assert!((reports[0].avg_ms.unwrap() - 109.09).abs() < 0.01);
+
+        assert_eq!(reports[1].key, 'b');
+        assert!(reports[1].earlier_acc.is_none());
+    }
+
+    #[test]
+    fn test_key_reports_ignore_old_rows() {
+        let lines = sample_csv_lines();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        assert!(compute_key_reports(&rows).is_empty());
+    }
+
+    #[test]
+    fn test_format_csv_line_wpm_values() {
+        let results = make_results(6.5, 380, 400, vec![], vec![]);
+
+        let line = format_csv_line("2026-02-14 12:00:00", "test", 50, "", &results);
+        let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+
+        assert_eq!(fields[3], "78.0"); // 6.5 * 12 = 78.0
+        assert_eq!(fields[4], "74.1"); // 78.0 * 0.95 = 74.1
+        assert_eq!(fields[5], "95.0"); // 380/400 = 95%
+    }
+
+    #[test]
+    fn test_format_csv_line_escapes_commas() {
+        let results = make_results(5.0, 90, 100, vec![(',', 1, 2)], vec!["hello,", "world"]);
+
+        let line = format_csv_line("2026-02-14 12:00:00", "test", 50, "", &results);
+        assert_eq!(line.split(',').count(), CSV_FIELDS);
+
+        let record = record::Record::parse(&line).unwrap();
+        assert_eq!(record.worst_keys, vec![",:50%"]);
+        assert_eq!(record.missed_words, vec!["hello,", "world"]);
+        assert!(verify::check(&format!("{}\n{}\n", CSV_HEADER, line)).is_empty());
+    }
+
+    #[test]
+    fn test_format_csv_line_escapes_language() {
+        let results = make_results(5.0, 90, 100, vec![], vec![]);
+        let line = format_csv_line("2026-02-14 12:00:00", "my,words", 50, "", &results);
+        assert_eq!(line.split(',').count(), CSV_FIELDS);
+
+        let lines = [line.as_str()];
+        let filters = Filters {
+            language: Some("my,words"),
+            since: None,
+            until: None,
+            tag: None,
+        };
+        let (rows, _) = parse_history_rows(&lines, &filters);
+        assert_eq!(rows[0].language, "my,words");
+    }
+
+    #[test]
+    fn test_format_csv_line_empty_missed_words() {
+        let results = make_results(5.0, 100, 100, vec![], vec![]);
+
+        let line = format_csv_line("2026-02-14 12:00:00", "test", 50, "", &results);
+        let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+
+        assert_eq!(fields[9], "", "missed_words should be empty");
+        assert_eq!(fields[10], "", "dwell should be empty when no data");
+        assert_eq!(
+            fields[11], "",
+            "key_stats should be empty without keypresses"
+        );
+    }
+
+    #[test]
+    fn test_format_csv_line_with_dwell_data() {
+        let mut results = make_results(5.0, 100, 100, vec![], vec![]);
+        results.dwell = DwellData {
+            per_key: vec![('a', 95.0), ('b', 110.0)],
+            overall_avg_ms: Some(102.5),
+            has_data: true,
+        };
+
+        let line = format_csv_line("2026-02-14 12:00:00", "test", 50, "", &results);
+        let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+
+        assert_eq!(fields[10], "102.5", "avg_dwell_ms should be present");
+    }
+
+    // --- File I/O integration ---
+
+    #[test]
+    fn test_save_creates_header_for_new_file() {
+        let dir = std::env::temp_dir().join("ttyper_test_header");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("history.csv");
+
+        let results = make_results(5.0, 100, 100, vec![], vec![]);
+        save_results(&file, "test", 50, "", &results).unwrap();
+
+        let content = fs::read_to_string(&file).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines.len(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_appends_without_duplicate_header() {
+        let dir = std::env::temp_dir().join("ttyper_test_append");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("history.csv");
+
+        let results = make_results(5.0, 100, 100, vec![], vec![]);
+        save_results(&file, "test", 50, "", &results).unwrap();
+        save_results(&file, "test", 50, "", &results).unwrap();
+
+        let content = fs::read_to_string(&file).unwrap();
+        let header_count = content
+            .lines()
+            .filter(|l| l.starts_with("datetime,"))
+            .count();
+        assert_eq!(header_count, 1);
+        assert_eq!(content.lines().count(), 3);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_concurrent_saves_write_one_header() {
+        let dir = std::env::temp_dir().join("ttyper_test_concurrent");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("history.csv");
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let file = file.clone();
+                std::thread::spawn(move || {
+                    let results = make_results(5.0, 100, 100, vec![('a', 1, 2)], vec![]);
+                    for _ in 0..10 {
+                        save_results(&file, "test", 50, "", &results).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let content = fs::read_to_string(&file).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines.len(), 81);
+        assert!(lines[1..].iter().all(|l| record::Record::parse(l).is_ok()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rewrite_with_concurrent_saves_keeps_all_rows() {
+        let dir = std::env::temp_dir().join("ttyper_test_rewrite_concurrent");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("history.csv");
+        let results = make_results(5.0, 100, 100, vec![], vec![]);
+        save_results(&file, "test", 50, "", &results).unwrap();
+
+        let saver = {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                for _ in 0..20 {
+                    save_results(&file, "test", 50, "", &results).unwrap();
+                }
+            })
+        };
+        for _ in 0..20 {
+            let lock = open_locked(&file).unwrap();
+            let content = read_locked(&lock).unwrap();
+            let lines: Vec<String> = content.lines().skip(1).map(str::to_string).collect();
+            rewrite_rows(&lock, &lines).unwrap();
+        }
+        saver.join().unwrap();
+
+        let content = read_shared(&file).unwrap();
+        assert_eq!(content.lines().count(), 22);
+        assert!(!dir.join("history.csv.tmp").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_reports_errors() {
+        let file = std::env::temp_dir()
+            .join("ttyper_test_missing_dir")
+            .join("nested")
+            .join("history.csv");
+        let results = make_results(5.0, 100, 100, vec![], vec![]);
+        assert!(save_results(&file, "test", 50, "", &results).is_err());
+    }
+
+    #[test]
+    fn test_save_counts_first_daily_attempt_only() {
+        let dir = std::env::temp_dir().join("ttyper_test_daily_save");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("history.csv");
+        let results = make_results(5.0, 100, 100, vec![], vec![]);
+
+        let daily = "seed=20260210;daily=2026-02-10";
+        assert!(save_results(&file, "test", 50, daily, &results).unwrap());
+        assert!(!save_results(&file, "test", 50, daily, &results).unwrap());
+        let next = "seed=20260211;daily=2026-02-11";
+        assert!(save_results(&file, "test", 50, next, &results).unwrap());
+        assert_eq!(fs::read_to_string(&file).unwrap().lines().count(), 3);
+
+        // An unreadable history is an error, not a first attempt
+        fs::write(&file, b"datetime\n\xff\n").unwrap();
+        assert!(save_results(&file, "test", 50, daily, &results).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // --- History display limiting and filtering ---
+
+    const NO_FILTERS: Filters<'static> = Filters {
+        language: None,
+        since: None,
+        until: None,
+        tag: None,
+    };
+
+    fn sample_csv_lines() -> Vec<&'static str> {
+        vec![
+            "2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,a:90%,",
+            "2026-02-11 10:00:00,english,50,75.0,71.2,95.0,190,200,,",
+            "2026-02-12 10:00:00,peter1000,50,78.0,74.1,95.0,380,400,y:50%,hello",
+            "2026-02-13 10:00:00,peter1000,50,80.0,76.0,95.0,380,400,,",
+            "2026-02-14 10:00:00,peter1000,50,82.0,77.9,95.0,380,400,,world",
+        ]
+    }
+
+    #[test]
+    fn test_last_limits_to_n_entries() {
+        let lines = sample_csv_lines();
+        let rows = format_history_rows(&lines, Some(2), &NO_FILTERS);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].1.starts_with("2026-02-13"));
+        assert!(rows[1].1.starts_with("2026-02-14"));
+        assert_eq!((rows[0].0, rows[1].0), (4, 5), "IDs count all rows");
+    }
+
+    #[test]
+    fn test_last_larger_than_total_shows_all() {
+        let lines = sample_csv_lines();
+        let rows = format_history_rows(&lines, Some(100), &NO_FILTERS);
+        assert_eq!(rows.len(), 5);
+        assert!(rows[0].1.starts_with("2026-02-10"));
+    }
+
+    #[test]
+    fn test_last_none_shows_all() {
+        let lines = sample_csv_lines();
+        let rows = format_history_rows(&lines, None, &NO_FILTERS);
+        assert_eq!(rows.len(), 5);
+    }
+
+    #[test]
+    fn test_last_zero_shows_nothing() {
+        let lines = sample_csv_lines();
+        let rows = format_history_rows(&lines, Some(0), &NO_FILTERS);
+        assert_eq!(rows.len(), 0);
+    }
+
+    // --- Language filtering ---
+
+    #[test]
+    fn test_filter_by_language() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: Some("peter1000"),
+            since: None,
+            until: None,
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].1.starts_with("2026-02-12"));
+        assert!(rows[2].1.starts_with("2026-02-14"));
+    }
+
+    #[test]
+    fn test_filter_by_language_no_match() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: Some("german"),
+            since: None,
+            until: None,
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 0);
+    }
+
+    #[test]
+    fn test_filter_by_tag() {
+        let lines = vec![
+            "2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,,,,,new keyboard;tired,",
+            "2026-02-11 10:00:00,english,50,75.0,71.2,95.0,190,200,,,,,tired,slept badly",
+            "2026-02-12 10:00:00,english,50,78.0,74.1,95.0,380,400,,",
+        ];
+        let filters = Filters {
+            language: None,
+            since: None,
+            until: None,
+            tag: Some("tired"),
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].1.ends_with("#new keyboard #tired"));
+        assert!(rows[1].1.ends_with("#tired \"slept badly\""));
+        assert_eq!(parse_history_rows(&lines, &filters).0.len(), 2);
+    }
+
+    #[test]
+    fn test_rows_show_word_options() {
+        let lines = vec![
+            "2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,,,,,tired,,range=100..200;min_len=4",
+            "2026-02-11 10:00:00,english,50,75.0,71.2,95.0,190,200,,",
+        ];
+        let rows = format_history_rows(&lines, None, &NO_FILTERS);
+        assert!(rows[0].1.ends_with(" [range=100..200;min_len=4] #tired"));
+        assert!(!rows[1].1.contains('['));
+    }
+
+    // --- Date filtering ---
+
+    #[test]
+    fn test_filter_since() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: None,
+            since: Some("2026-02-13"),
+            until: None,
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].1.starts_with("2026-02-13"));
+        assert!(rows[1].1.starts_with("2026-02-14"));
+    }
+
+    #[test]
+    fn test_filter_until() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: None,
+            since: None,
+            until: Some("2026-02-11"),
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].1.starts_with("2026-02-10"));
+        assert!(rows[1].1.starts_with("2026-02-11"));
+    }
+
+    #[test]
+    fn test_filter_date_range() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: None,
+            since: Some("2026-02-11"),
+            until: Some("2026-02-13"),
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].1.starts_with("2026-02-11"));
+        assert!(rows[2].1.starts_with("2026-02-13"));
+    }
+
+    // --- Combined filters ---
+
+    #[test]
+    fn test_filter_language_and_date_range() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: Some("peter1000"),
+            since: Some("2026-02-13"),
+            until: None,
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].1.starts_with("2026-02-13"));
+        assert!(rows[1].1.starts_with("2026-02-14"));
+    }
+
+    #[test]
+    fn test_filter_with_last() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: Some("peter1000"),
+            since: None,
+            until: None,
+            tag: None,
+        };
+        // 3 peter1000 entries, take last 1
+        let rows = format_history_rows(&lines, Some(1), &filters);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].1.starts_with("2026-02-14"));
+    }
+
+    #[test]
+    fn test_filter_same_day_range() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: None,
+            since: Some("2026-02-12"),
+            until: Some("2026-02-12"),
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].1.starts_with("2026-02-12"));
+    }
+
+    // --- Date validation ---
+
+    #[test]
+    fn test_validate_date_valid() {
+        assert!(validate_date_format("2026-02-14").is_ok());
+        assert!(validate_date_format("2000-01-01").is_ok());
+    }
+
+    #[test]
+    fn test_validate_date_invalid_format() {
+        assert!(validate_date_format("2026-2-5").is_err());
+        assert!(validate_date_format("14-02-2026").is_err());
+        assert!(validate_date_format("not-a-date").is_err());
+        assert!(validate_date_format("2026/02/14").is_err());
+        assert!(validate_date_format("20260214").is_err());
+    }
+
+    // --- Short timestamp robustness ---
+
+    #[test]
+    fn test_filter_skips_malformed_short_timestamp() {
+        let lines = vec![
+            "short,english,50,72.0,68.4,95.0,190,200,a:90%,",
+            "2026-02-14 10:00:00,english,50,75.0,71.2,95.0,190,200,,",
+        ];
+        let filters = Filters {
+            language: None,
+            since: Some("2026-02-01"),
+            until: None,
+            tag: None,
+        };
+        let rows = format_history_rows(&lines, None, &filters);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].1.starts_with("2026-02-14"));
+    }
+
+    // --- Stats aggregation ---
+
+    #[test]
+    fn test_parse_history_rows() {
+        let lines = sample_csv_lines();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0].date, "2026-02-10");
+        assert_eq!(rows[0].language, "english");
+        assert!((rows[0].wpm_raw - 72.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_history_rows_counts_malformed_rows() {
+        let mut lines = sample_csv_lines();
+        lines.push("2026-02-15 10:00:00,english,lots,80.0,76.0,95.0,95,100,,");
+        lines.push("garbage");
+        let (rows, skipped) = parse_history_rows(&lines, &NO_FILTERS);
+        assert_eq!(rows.len(), 5);
+        assert_eq!(skipped, 2);
+
+        // Rows the filters exclude aren't counted, even when they're malformed
+        lines.push("2026-02-16 10:00:00,german,50");
+        lines.push("2026-02-1é 10:00:00,english,lots");
+        let filters = Filters {
+            language: Some("english"),
+            since: Some("2026-02-12"),
+            until: None,
+            tag: None,
+        };
+        let (rows, skipped) = parse_history_rows(&lines, &filters);
+        assert_eq!(rows.len(), 0);
+        assert_eq!(skipped, 1);
+    }
+
+    #[test]
+    fn test_parse_history_rows_with_language_filter() {
+        let lines = sample_csv_lines();
+        let filters = Filters {
+            language: Some("peter1000"),
+            since: None,
+            until: None,
+            tag: None,
+        };
+        let (rows, _) = parse_history_rows(&lines, &filters);
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|r| r.language == "peter1000"));
+    }
+
+    #[test]
+    fn test_compute_overall_stats() {
+        let lines = sample_csv_lines();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let (avg_raw, avg_adj, avg_acc, first_date, most_lang, most_count) =
+            compute_overall_stats(&rows);
+
+        // (72 + 75 + 78 + 80 + 82) / 5 = 77.4
+        assert!((avg_raw - 77.4).abs() < 0.01);
+        // (68.4 + 71.2 + 74.1 + 76.0 + 77.9) / 5 = 73.52
+        assert!((avg_adj - 73.52).abs() < 0.01);
+        assert!((avg_acc - 95.0).abs() < 0.01);
+        assert_eq!(first_date, "2026-02-10");
+        assert_eq!(most_lang, "peter1000");
+        assert_eq!(most_count, 3);
+    }
+
+    #[test]
+    fn test_compute_overall_stats_empty() {
+        let (avg_raw, _, _, _, _, _) = compute_overall_stats(&[]);
+        assert!((avg_raw - 0.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_weekly_trend() {
+        let lines = sample_csv_lines();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let weeks = weekly_trend(&rows);
+
+        assert!(!weeks.is_empty());
+        // All dates are in the same week (KW07 of 2026)
+        // Feb 10 = Mon of KW07, Feb 14 = Fri of KW07
+        assert_eq!(weeks.len(), 1);
+        assert_eq!(weeks[0].0, "W07");
+    }
+
+    #[test]
+    fn test_weekly_trend_multiple_weeks() {
+        let lines = vec![
+            "2026-01-27 10:00:00,english,50,70.0,66.5,95.0,190,200,,",
+            "2026-02-03 10:00:00,english,50,75.0,71.2,95.0,190,200,,",
+            "2026-02-10 10:00:00,english,50,80.0,76.0,95.0,190,200,,",
+        ];
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let weeks = weekly_trend(&rows);
+
+        assert_eq!(weeks.len(), 3);
+        // Should be sorted chronologically
+        assert!(weeks[0].1 < weeks[1].1);
+        assert!(weeks[1].1 < weeks[2].1);
+    }
+
+    // --- Daily challenge ---
+
+    #[test]
+    fn test_daily_scores_count_first_attempts() {
+        let lines = vec![
+            "2026-02-12 10:00:00,english1000,50,70.0,66.0,95.0,190,200,,,,,,,seed=20260212;daily=2026-02-12",
+            "2026-02-10 10:00:00,english1000,50,60.0,57.0,95.0,190,200,,,,,,,seed=20260210;daily=2026-02-10",
+            "2026-02-10 11:00:00,english1000,50,90.0,88.0,95.0,190,200,,,,,,,daily=2026-02-10",
+            "2026-02-11 10:00:00,english,50,80.0,76.0,95.0,190,200,,",
+        ];
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let scores = daily_scores(&rows);
+        let dates: Vec<String> = scores.iter().map(|(d, _)| d.to_string()).collect();
+        assert_eq!(dates, ["2026-02-10", "2026-02-12"]);
+        assert!((scores[0].1.wpm_adj - 57.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_daily_rows_only_count_for_daily_view() {
+        let lines = vec![
+            "2026-02-10 10:00:00,english1000,50,60.0,57.0,95.0,190,200,,,,a=1/2@100,,,seed=20260210;daily=2026-02-10",
+            "2026-02-10 11:00:00,english1000,50,90.0,88.0,95.0,190,200,,,,b=2/2@100,,,seed=5",
+        ];
+        let rows = || parse_history_rows(&lines, &NO_FILTERS).0;
+
+        let daily = rows_for_view(rows(), StatsView::Daily);
+        assert_eq!(daily.len(), 2);
+        for view in [
+            StatsView::Summary,
+            StatsView::Grouped(GroupBy::Language),
+            StatsView::Keys,
+        ] {
+            let kept = rows_for_view(rows(), view);
+            assert_eq!(kept.len(), 1);
+            assert_eq!(kept[0].wpm_adj, 88.0);
+        }
+        let keys: Vec<char> = compute_key_reports(&rows_for_view(rows(), StatsView::Keys))
+            .iter()
+            .map(|report| report.key)
+            .collect();
+        assert_eq!(keys, ['b']);
+    }
+
+    #[test]
+    fn test_daily_streaks() {
+        let date = |s: &str| s.parse::<chrono::NaiveDate>().unwrap();
+        let dates: Vec<_> = [
+            "2026-02-01",
+            "2026-02-02",
+            "2026-02-03",
+            "2026-02-05",
+            "2026-02-06",
+        ]
+        .iter()
+        .map(|d| date(d))
+        .collect();
+        assert_eq!(daily_streaks(&dates, date("2026-02-06")), (2, 3));
+        assert_eq!(daily_streaks(&dates, date("2026-02-07")), (2, 3));
+        assert_eq!(daily_streaks(&dates, date("2026-02-08")), (0, 3));
+        assert_eq!(daily_streaks(&[], date("2026-02-08")), (0, 0));
+    }
+
+    #[test]
+    fn test_option_value() {
+        assert_eq!(
+            option_value("top=5;daily=2026-02-10", "daily"),
+            Some("2026-02-10")
+        );
+        assert_eq!(option_value("top=5", "to"), None);
+        assert_eq!(option_value("", "daily"), None);
+    }
+
+    // --- Grouped stats ---
+
+    #[test]
+    fn test_group_by_language() {
+        let lines = sample_csv_lines();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let groups = compute_group_stats(&rows, GroupBy::Language);
+
+        assert_eq!(groups.len(), 2);
+        // Most practiced language first
+        assert_eq!(groups[0].key, "peter1000");
+        assert_eq!(groups[0].count, 3);
+        assert!((groups[0].best_wpm - 77.9).abs() < 0.01);
+        assert_eq!(groups[1].key, "english");
+        assert_eq!(groups[1].count, 2);
+        // (68.4 + 71.2) / 2 = 69.8
+        assert!((groups[1].avg_wpm - 69.8).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_group_trend_compares_halves() {
+        let lines = sample_csv_lines();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let groups = compute_group_stats(&rows, GroupBy::Language);
+
+        // english: 71.2 - 68.4 = 2.8
+        assert!((groups[1].trend.unwrap() - 2.8).abs() < 0.01);
+        // peter1000: avg(76.0, 77.9) - 74.1 = 2.85
+        assert!((groups[0].trend.unwrap() - 2.85).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_group_single_test_has_no_trend() {
+        let lines = vec!["2026-02-14 10:00:00,german,50,82.0,77.9,95.0,380,400,,"];
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let groups = compute_group_stats(&rows, GroupBy::Language);
+        assert!(groups[0].trend.is_none());
+        assert_eq!(format_trend(groups[0].trend), "-");
+    }
+
+    #[test]
+    fn test_group_by_words_sorted_numerically() {
+        let lines = vec![
+            "2026-02-10 10:00:00,english,100,72.0,68.4,95.0,190,200,,",
+            "2026-02-11 10:00:00,english,25,75.0,71.2,95.0,190,200,,",
+            "2026-02-12 10:00:00,english,50,78.0,74.1,95.0,380,400,,",
+        ];
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        let groups = compute_group_stats(&rows, GroupBy::Words);
+        let keys: Vec<&str> = groups.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(keys, vec!["25", "50", "100"]);
+    }
+
+    #[test]
+    fn test_group_by_week_and_month_across_years() {
+        let lines = vec![
+            "2025-12-30 10:00:00,english,50,70.0,66.5,95.0,190,200,,",
+            "2026-01-27 10:00:00,english,50,75.0,71.2,95.0,190,200,,",
+            "2026-02-10 10:00:00,english,50,80.0,76.0,95.0,190,200,,",
+        ];
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+
+        let weeks = compute_group_stats(&rows, GroupBy::Week);
+        let keys: Vec<&str> = weeks.iter().map(|g| g.key.as_str()).collect();
+        // Dec 30, 2025 belongs to ISO week 1 of 2026
+        assert_eq!(keys, vec!["2026-W01", "2026-W05", "2026-W07"]);
+
+        let months = compute_group_stats(&rows, GroupBy::Month);
+        let keys: Vec<&str> = months.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(keys, vec!["2025-12", "2026-01", "2026-02"]);
+    }
+
+    #[test]
+    fn test_format_trend() {
+        assert_eq!(format_trend(Some(3.24)), "+3.2 ^");
+        assert_eq!(format_trend(Some(-1.5)), "-1.5 v");
+        assert_eq!(format_trend(Some(0.0)), "0.0");
+    }
+
+    // --- Dwell CSV backward compatibility ---
+
+    #[test]
+    fn test_parse_old_csv_without_dwell() {
+        // Old format: 10 fields, no dwell column
+        let lines = sample_csv_lines();
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        assert!(rows.iter().all(|r| r.avg_dwell_ms.is_none()));
+    }
+
+    #[test]
+    fn test_parse_new_csv_with_dwell() {
+        let lines = vec!["2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,,world,98.5"];
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        assert_eq!(rows.len(), 1);
+        assert!((rows[0].avg_dwell_ms.unwrap() - 98.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_mixed_csv_old_and_new() {
+        let lines = vec![
+            "2026-02-13 10:00:00,english,50,80.0,76.0,95.0,380,400,,",
+            "2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,,,102.3",
+        ];
+        let (rows, _) = parse_history_rows(&lines, &NO_FILTERS);
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].avg_dwell_ms.is_none());
+        assert!((rows[1].avg_dwell_ms.unwrap() - 102.3).abs() < 0.01);
+    }
+}
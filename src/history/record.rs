@@ -0,0 +1,212 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{
+    escape_text, format_list, format_worst_key_list, option_value, parse_key_stats, parse_list,
+    parse_worst_key_list, unescape_text, CSV_FIELDS,
+};
+
+/// A single history entry with every column of the native CSV format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub datetime: String,
+    pub language: String,
+    pub words: usize,
+    pub wpm_raw: f64,
+    pub wpm_adjusted: f64,
+    pub accuracy: f64,
+    pub correct: usize,
+    pub total: usize,
+    /// Entries like `y:50%`, worst first.
+    #[serde(default, deserialize_with = "list_or_column")]
+    pub worst_keys: Vec<String>,
+    #[serde(default, deserialize_with = "list_or_column")]
+    pub missed_words: Vec<String>,
+    #[serde(default)]
+    pub avg_dwell_ms: Option<f64>,
+    #[serde(default)]
+    pub key_stats: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub note: String,
+    /// How the words were chosen, like `top=500;min_len=4`.
+    #[serde(default)]
+    pub options: String,
+}
+
+impl Record {
+    /// Parse a native history line. Older rows without trailing columns are accepted.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.splitn(CSV_FIELDS, ',').collect();
+        Self::from_fields(&fields)
+    }
+
+    /// Build a record from fields in native column order.
+    pub fn from_fields(fields: &[&str]) -> Result<Self, String> {
+        if fields.len() < 9 {
+            return Err(format!(
+                "expected at least 9 fields, found {}",
+                fields.len()
+            ));
+        }
+        if chrono::NaiveDateTime::parse_from_str(fields[0], "%Y-%m-%d %H:%M:%S").is_err() {
+            return Err(format!("invalid datetime '{}'", fields[0]));
+        }
+        // Extra commas end up in the last column when rows are concatenated
+        if fields.len() > CSV_FIELDS || fields.get(CSV_FIELDS - 1).is_some_and(|s| s.contains(','))
+        {
+            return Err("too many fields".to_string());
+        }
+        let key_stats = fields.get(11).unwrap_or(&"");
+        if parse_key_stats(key_stats).len() != key_stats.split_terminator(';').count() {
+            return Err(format!("invalid key_stats '{}'", key_stats));
+        }
+
+        fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("invalid {} '{}'", name, value))
+        }
+
+        Ok(Self {
+            datetime: fields[0].to_string(),
+            language: unescape_text(fields[1]),
+            words: number("words", fields[2])?,
+            wpm_raw: number("wpm_raw", fields[3])?,
+            wpm_adjusted: number("wpm_adjusted", fields[4])?,
+            accuracy: number("accuracy", fields[5])?,
+            correct: number("correct", fields[6])?,
+            total: number("total", fields[7])?,
+            worst_keys: parse_worst_key_list(fields[8]),
+            missed_words: fields.get(9).map_or(Vec::new(), |f| parse_list(f)),
+            avg_dwell_ms: match fields.get(10) {
+                Some(s) if !s.is_empty() => Some(number("avg_dwell_ms", s)?),
+                _ => None,
+            },
+            key_stats: key_stats.to_string(),
+            tags: fields.get(12).map_or(Vec::new(), |f| parse_list(f)),
+            note: fields.get(13).map_or(String::new(), |f| unescape_text(f)),
+            options: fields.get(14).unwrap_or(&"").to_string(),
+        })
+    }
+
+    /// Format as a native history line (same layout as `format_csv_line`).
+    pub fn to_csv_line(&self) -> String {
+        self.to_fields().join(",")
+    }
+
+    /// Columns of a native history line, with free text and lists escaped.
+    pub fn to_fields(&self) -> Vec<String> {
+        vec![
+            self.datetime.clone(),
+            escape_text(&self.language),
+            self.words.to_string(),
+            format!("{:.1}", self.wpm_raw),
+            format!("{:.1}", self.wpm_adjusted),
+            format!("{:.1}", self.accuracy),
+            self.correct.to_string(),
+            self.total.to_string(),
+            format_worst_key_list(&self.worst_keys),
+            format_list(&self.missed_words),
+            self.avg_dwell_ms
+                .map_or(String::new(), |ms| format!("{:.1}", ms)),
+            self.key_stats.clone(),
+            format_list(&self.tags),
+            escape_text(&self.note),
+            self.options.clone(),
+        ]
+    }
+
+    /// Columns that identify a test result, used to detect duplicates when merging.
+    /// Imported Monkeytype results are identified by their source timestamp, as their
+    /// datetime is in the time zone they were imported in.
+    pub fn identity(&self) -> String {
+        if let Some(timestamp) = option_value(&self.options, "monkeytype") {
+            return format!("monkeytype={}", timestamp);
+        }
+        format!(
+            "{},{},{},{:.1},{:.1},{:.1},{},{}",
+            self.datetime,
+            self.language,
+            self.words,
+            self.wpm_raw,
+            self.wpm_adjusted,
+            self.accuracy,
+            self.correct,
+            self.total,
+        )
+    }
+}
+
+/// Accept a list column as a list, or as the `;`-separated string older exports wrote.
+fn list_or_column<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ListOrColumn {
+        List(Vec<String>),
+        Column(String),
+    }
+
+    Ok(match ListOrColumn::deserialize(deserializer)? {
+        ListOrColumn::List(items) => items,
+        ListOrColumn::Column(column) => parse_list(&column),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_roundtrips_current_format() {
+        let line =
+            "2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,y:50%,world,98.5,a=1/2@90,,,top=1000";
+        let record = Record::parse(line).unwrap();
+        assert_eq!(record.words, 50);
+        assert_eq!(record.options, "top=1000");
+        assert_eq!(record.avg_dwell_ms, Some(98.5));
+        assert_eq!(record.to_csv_line(), line);
+    }
+
+    #[test]
+    fn parse_roundtrips_tags_and_note() {
+        let line = "2026-02-14 10:00:00,english,50,82.0,77.9,95.0,380,400,,,,,new keyboard;a%3Bb,tired%2C late,";
+        let record = Record::parse(line).unwrap();
+        assert_eq!(record.tags, vec!["new keyboard", "a;b"]);
+        assert_eq!(record.note, "tired, late");
+        assert_eq!(record.to_csv_line(), line);
+    }
+
+    #[test]
+    fn parse_roundtrips_language_with_separators() {
+        let line = "2026-02-14 10:00:00,words%2C mine%3B v2,50,82.0,77.9,95.0,380,400,,,,,,,";
+        let record = Record::parse(line).unwrap();
+        assert_eq!(record.language, "words, mine; v2");
+        assert_eq!(record.to_csv_line(), line);
+    }
+
+    #[test]
+    fn parse_accepts_old_format() {
+        let record =
+            Record::parse("2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,a:90%,").unwrap();
+        assert!(record.missed_words.is_empty());
+        assert_eq!(record.avg_dwell_ms, None);
+        assert_eq!(record.key_stats, "");
+        assert!(record.tags.is_empty());
+        assert_eq!(record.note, "");
+        assert_eq!(record.options, "");
+    }
+
+    #[test]
+    fn parse_rejects_malformed() {
+        assert!(Record::parse("2026-02-10 10:00:00,english,50").is_err());
+        assert!(
+            Record::parse("2026-02-10 10:00:00,english,fifty,72.0,68.4,95.0,190,200,").is_err()
+        );
+        assert!(Record::parse("short,english,50,72.0,68.4,95.0,190,200,").is_err());
+        assert!(Record::parse(
+            "2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,,,,a=1/1@902026-02-10 10:00:01,english"
+        )
+        .is_err());
+    }
+}
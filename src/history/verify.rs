@@ -0,0 +1,319 @@
+//! Detection and repair of malformed history rows.
+//!
+//! Older versions wrote history without locking, so concurrent instances could
+//! duplicate the header or concatenate two rows on one line. They also wrote the
+//! `worst_keys` and `missed_words` columns without escaping, so a `,` key or a missed
+//! word with a comma added columns to a row.
+
+use super::record::Record;
+use super::{open_locked, read_locked, read_shared, rewrite_rows, CSV_FIELDS};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A problem found on a specific (1-based) line of the history file.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Summary of a repair run.
+#[derive(Debug, Default, PartialEq)]
+pub struct Repair {
+    /// Data lines of the repaired file, without header.
+    pub lines: Vec<String>,
+    /// Rows that were valid as-is.
+    pub kept: usize,
+    /// Rows split out of concatenated lines or rewritten with their commas escaped.
+    pub recovered: usize,
+    /// Lines dropped because they could not be parsed (including duplicate headers).
+    pub removed: usize,
+}
+
+fn is_header(line: &str) -> bool {
+    line.starts_with("datetime,")
+}
+
+/// Check every line of a history file and report problems.
+pub fn check(content: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let message = if i == 0 {
+            if is_header(line) {
+                continue;
+            }
+            "missing header".to_string()
+        } else if line.trim().is_empty() {
+            "empty line".to_string()
+        } else if is_header(line) {
+            "duplicate header".to_string()
+        } else {
+            match Record::parse(line) {
+                Ok(_) => continue,
+                Err(_) if unescaped_row(line).is_some() => {
+                    "unescaped comma in worst_keys or missed_words".to_string()
+                }
+                Err(e) => e,
+            }
+        };
+        problems.push(Problem {
+            line: i + 1,
+            message,
+        });
+    }
+
+    problems
+}
+
+/// Whether `s` starts with a "YYYY-MM-DD HH:MM:SS," timestamp field.
+fn starts_with_timestamp(s: &str) -> bool {
+    const PATTERN: &[u8] = b"dddd-dd-dd dd:dd:dd,";
+    s.len() >= PATTERN.len()
+        && s.bytes().zip(PATTERN).all(|(b, &p)| match p {
+            b'd' => b.is_ascii_digit(),
+            p => b == p,
+        })
+}
+
+/// Split a line at every embedded timestamp, undoing rows concatenated by
+/// interleaved writes.
+fn split_at_timestamps(line: &str) -> Vec<&str> {
+    let starts: Vec<usize> = line
+        .char_indices()
+        .map(|(i, _)| i)
+        .filter(|&i| starts_with_timestamp(&line[i..]))
+        .collect();
+
+    if starts.is_empty() {
+        return vec![line];
+    }
+
+    let mut pieces = Vec::new();
+    let mut bounds = starts.clone();
+    bounds.push(line.len());
+    if starts[0] > 0 {
+        pieces.push(&line[..starts[0]]);
+    }
+    for window in bounds.windows(2) {
+        pieces.push(&line[window[0]..window[1]]);
+    }
+    pieces
+}
+
+/// Rebuild a row written before `worst_keys` and `missed_words` were escaped, where
+/// the commas in them added columns. Returns None for other malformed rows.
+fn unescaped_row(line: &str) -> Option<Record> {
+    if split_at_timestamps(line).len() > 1 {
+        return None;
+    }
+    let pieces: Vec<&str> = line.split(',').collect();
+    // Rows have had 11 to 15 columns, and the ones after `missed_words` never contain commas
+    (11..=CSV_FIELDS).rev().find_map(|columns| {
+        let trailing = columns - 10;
+        let middle = pieces.get(8..pieces.len().checked_sub(trailing)?)?;
+        if middle.len() <= 2 {
+            return None;
+        }
+        // A `,` key leaves its accuracy, like `:50%`, at the start of the next piece
+        let worst_len = 1 + middle[1..middle.len() - 1]
+            .iter()
+            .take_while(|piece| {
+                piece
+                    .strip_prefix(':')
+                    .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .count();
+        let worst_keys = middle[..worst_len].join(",");
+        let missed_words = middle[worst_len..].join(",");
+
+        let mut fields = pieces[..8].to_vec();
+        fields.push(&worst_keys);
+        fields.push(&missed_words);
+        fields.extend_from_slice(&pieces[pieces.len() - trailing..]);
+        Record::from_fields(&fields).ok()
+    })
+}
+
+/// Repair a history file's content: drop duplicate headers and empty lines,
+/// split concatenated rows, escape commas older versions left in rows and remove
+/// rows that still can't be parsed.
+pub fn repair(content: &str) -> Repair {
+    let mut repair = Repair::default();
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || (i == 0 && is_header(line)) {
+            continue;
+        }
+        if is_header(line) {
+            repair.removed += 1;
+            continue;
+        }
+        if Record::parse(line).is_ok() {
+            repair.lines.push(line.to_string());
+            repair.kept += 1;
+            continue;
+        }
+        if let Some(record) = unescaped_row(line) {
+            repair.lines.push(record.to_csv_line());
+            repair.recovered += 1;
+            continue;
+        }
+        for piece in split_at_timestamps(line) {
+            if Record::parse(piece).is_ok() {
+                repair.lines.push(piece.to_string());
+                repair.recovered += 1;
+            } else {
+                repair.removed += 1;
+            }
+        }
+    }
+
+    repair
+}
+
+/// Check a history file. A missing file has no problems.
+pub fn verify_file(history_file: &Path) -> Result<Vec<Problem>, String> {
+    if !history_file.exists() {
+        return Ok(Vec::new());
+    }
+    let content = read_shared(history_file).map_err(|e| {
+        format!(
+            "Error: Cannot read history file '{}': {}",
+            history_file.display(),
+            e
+        )
+    })?;
+    Ok(check(&content))
+}
+
+/// Repair a history file in place while holding its lock. The original is saved
+/// next to it with a `.bak` extension. Returns None when there was nothing to fix.
+pub fn repair_file(history_file: &Path) -> Result<Option<(Repair, PathBuf)>, String> {
+    if !history_file.exists() {
+        return Ok(None);
+    }
+    let io_error = |e: std::io::Error| {
+        format!(
+            "Error: Cannot repair history file '{}': {}",
+            history_file.display(),
+            e
+        )
+    };
+
+    let lock = open_locked(history_file).map_err(io_error)?;
+    let content = read_locked(&lock).map_err(io_error)?;
+    if check(&content).is_empty() {
+        return Ok(None);
+    }
+
+    let backup = history_file.with_extension("csv.bak");
+    fs::write(&backup, &content).map_err(io_error)?;
+
+    let repair = repair(&content);
+    rewrite_rows(&lock, &repair.lines).map_err(io_error)?;
+
+    Ok(Some((repair, backup)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::CSV_HEADER;
+
+    const ROW_A: &str = "2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,a:90%,";
+    const ROW_B: &str = "2026-02-11 10:00:00,english,50,75.0,71.2,95.0,190,200,,,,a=1/1@90";
+
+    #[test]
+    fn check_accepts_valid_file() {
+        let content = format!("{}\n{}\n{}\n", CSV_HEADER, ROW_A, ROW_B);
+        assert!(check(&content).is_empty());
+    }
+
+    #[test]
+    fn check_reports_line_numbers() {
+        let content = format!(
+            "{}\n{}\n{}\n\n{}{}\ngarbage\n",
+            CSV_HEADER, ROW_A, CSV_HEADER, ROW_A, ROW_B
+        );
+        let problems = check(&content);
+        let lines: Vec<usize> = problems.iter().map(|p| p.line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6]);
+        assert_eq!(problems[0].message, "duplicate header");
+        assert_eq!(problems[1].message, "empty line");
+    }
+
+    #[test]
+    fn check_reports_missing_header() {
+        let problems = check(&format!("{}\n", ROW_A));
+        assert_eq!(problems[0].message, "missing header");
+    }
+
+    #[test]
+    fn repair_splits_concatenated_rows() {
+        let content = format!(
+            "{}\n{}\n{}\n{}{}\ngarbage\n",
+            CSV_HEADER, ROW_A, CSV_HEADER, ROW_B, ROW_A
+        );
+        let repair = repair(&content);
+        assert_eq!(repair.lines, vec![ROW_A, ROW_B, ROW_A]);
+        assert_eq!(repair.kept, 1);
+        assert_eq!(repair.recovered, 2);
+        // duplicate header + garbage
+        assert_eq!(repair.removed, 2);
+    }
+
+    #[test]
+    fn repair_escapes_commas_in_old_rows() {
+        // A `,` key and a missed word with a comma, written before they were escaped
+        let comma_key =
+            "2026-02-12 10:00:00,english,50,75.0,71.2,95.0,190,200,y:50%;,:60%,hello,98.5,,,,";
+        let comma_word =
+            "2026-02-13 10:00:00,english,50,75.0,71.2,95.0,190,200,a:90%,hello,;world,98.5";
+        let content = format!("{}\n{}\n{}\n{}\n", CSV_HEADER, ROW_A, comma_key, comma_word);
+
+        let problems = check(&content);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(
+            problems[0].message,
+            "unescaped comma in worst_keys or missed_words"
+        );
+
+        let repair = repair(&content);
+        assert_eq!(repair.removed, 0);
+        assert_eq!(repair.recovered, 2);
+        let records: Vec<Record> = repair
+            .lines
+            .iter()
+            .map(|line| Record::parse(line).unwrap())
+            .collect();
+        assert_eq!(records[1].worst_keys, vec!["y:50%", ",:60%"]);
+        assert_eq!(records[1].missed_words, vec!["hello"]);
+        assert_eq!(records[1].avg_dwell_ms, Some(98.5));
+        assert_eq!(records[2].worst_keys, vec!["a:90%"]);
+        assert_eq!(records[2].missed_words, vec!["hello,", "world"]);
+        assert_eq!(records[2].avg_dwell_ms, Some(98.5));
+    }
+
+    #[test]
+    fn repair_file_writes_backup() {
+        let dir = std::env::temp_dir().join("ttyper_test_repair");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("history.csv");
+        let original = format!("{}\n{}\n{}\n", CSV_HEADER, ROW_A, CSV_HEADER);
+        fs::write(&file, &original).unwrap();
+
+        let (repair, backup) = repair_file(&file).unwrap().unwrap();
+        assert_eq!(repair.removed, 1);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            format!("{}\n{}\n", CSV_HEADER, ROW_A)
+        );
+        assert!(repair_file(&file).unwrap().is_none(), "nothing left to fix");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,408 @@
+//! Language files: a word on each line, optionally after a TOML header between two
+//! `+++` lines that describes the language and how to pick its words.
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::SliceRandom,
+    Rng,
+};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+const HEADER_DELIMITER: &str = "+++";
+
+/// Information about a language from the header of its file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Metadata {
+    /// Name to show instead of the file name
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub direction: Direction,
+    pub weights: Weights,
+    /// Words are in a fixed order, so they are never shuffled
+    pub ordered: bool,
+}
+
+/// Script direction. ttyper leaves displaying right-to-left text to the terminal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+/// How often each word is picked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weights {
+    /// All words are equally likely.
+    #[default]
+    None,
+    /// Each line is a word followed by its weight, like `und 1000`.
+    Inline,
+    /// Words are sorted by frequency, most common first. The weight of a word is
+    /// one over its rank.
+    Rank,
+}
+
+impl Metadata {
+    /// Read only the header of a language file.
+    pub fn read(source: &str) -> Result<Metadata, String> {
+        match split_header(source)?.0 {
+            Some(header) => parse_header(header),
+            None => Ok(Metadata::default()),
+        }
+    }
+
+    /// One line about the language, for listings.
+    pub fn summary(&self) -> String {
+        let parts: Vec<&str> = self
+            .name
+            .iter()
+            .chain(&self.description)
+            .map(String::as_str)
+            .collect();
+        let mut summary = parts.join(": ");
+        if self.direction == Direction::Rtl {
+            summary.push_str(" (right-to-left)");
+        }
+        summary
+    }
+}
+
+/// Ranks of words in a list, counting from 1. Written `A..B`, or `A..` to the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Band {
+    pub first: usize,
+    pub last: Option<usize>,
+}
+
+impl Band {
+    /// The `n` most common words.
+    pub fn top(n: usize) -> Band {
+        Band {
+            first: 1,
+            last: Some(n),
+        }
+    }
+
+    fn contains(&self, rank: usize) -> bool {
+        rank >= self.first && self.last.map_or(true, |last| rank <= last)
+    }
+}
+
+impl FromStr for Band {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || "expected A..B or A.. with ranks from 1 (e.g., 100..200)".to_string();
+        let (first, last) = s.split_once("..").ok_or_else(error)?;
+        let first = first.parse().ok().filter(|&n| n >= 1).ok_or_else(error)?;
+        let last = match last {
+            "" => None,
+            last => Some(
+                last.parse()
+                    .ok()
+                    .filter(|&n| n >= first)
+                    .ok_or_else(error)?,
+            ),
+        };
+        Ok(Band { first, last })
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..", self.first)?;
+        match self.last {
+            Some(last) => write!(f, "{}", last),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Language {
+    pub metadata: Metadata,
+    pub words: Vec<String>,
+    /// Weight of each word, if the language has weights.
+    weights: Option<Vec<f64>>,
+}
+
+impl Language {
+    pub fn parse(source: &str) -> Result<Language, String> {
+        let (header, body) = split_header(source)?;
+        let metadata = match header {
+            Some(header) => parse_header(header)?,
+            None => Metadata::default(),
+        };
+
+        let (words, weights) = match metadata.weights {
+            Weights::None => (body.lines().map(ToOwned::to_owned).collect(), None),
+            Weights::Rank => {
+                let words: Vec<String> = body
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect();
+                let weights = (1..=words.len()).map(|rank| 1.0 / rank as f64).collect();
+                (words, Some(weights))
+            }
+            Weights::Inline => {
+                // Line numbers in errors count from the top of the file
+                let first_line = source[..source.len() - body.len()].lines().count() + 1;
+                let mut words = Vec::new();
+                let mut weights = Vec::new();
+                for (i, line) in body.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let (word, weight) = line
+                        .trim_end()
+                        .rsplit_once(char::is_whitespace)
+                        .and_then(|(word, weight)| Some((word.trim_end(), weight.parse().ok()?)))
+                        .filter(|(word, weight): &(&str, f64)| {
+                            !word.is_empty() && weight.is_finite() && *weight >= 0.0
+                        })
+                        .ok_or_else(|| {
+                            format!(
+                                "line {}: expected a word and a weight, got '{}'",
+                                first_line + i,
+                                line
+                            )
+                        })?;
+                    words.push(word.to_owned());
+                    weights.push(weight);
+                }
+                (words, Some(weights))
+            }
+        };
+
+        Ok(Language {
+            metadata,
+            words,
+            weights,
+        })
+    }
+
+    /// Keep only the words in a rank band, with lengths in characters between
+    /// `min_len` and `max_len`.
+    pub fn select(&mut self, band: Option<Band>, min_len: Option<usize>, max_len: Option<usize>) {
+        self.retain(|rank, word| {
+            let len = word.chars().count();
+            band.map_or(true, |band| band.contains(rank))
+                && min_len.map_or(true, |min| len >= min)
+                && max_len.map_or(true, |max| len <= max)
+        });
+    }
+
+    /// Keep only the words for which `keep` returns true, given their rank counting
+    /// from 1.
+    pub fn retain(&mut self, mut keep: impl FnMut(usize, &str) -> bool) {
+        let keep: Vec<bool> = self
+            .words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| keep(i + 1, word))
+            .collect();
+        let mut kept = keep.iter();
+        self.words.retain(|_| *kept.next().unwrap());
+        if let Some(weights) = &mut self.weights {
+            let mut kept = keep.iter();
+            weights.retain(|_| *kept.next().unwrap());
+        }
+    }
+
+    /// Each word with its weight, which is 1 if the language has no weights.
+    pub fn weighted_words(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.words.iter().enumerate().map(|(i, word)| {
+            let weight = self.weights.as_ref().map_or(1.0, |weights| weights[i]);
+            (word.as_str(), weight)
+        })
+    }
+
+    /// Pick `count` words, or every word once if `count` is None. Words are picked by
+    /// weight if the language has weights, and shuffled unless `shuffle` is false or
+    /// the language is ordered.
+    pub fn sample(
+        &self,
+        count: Option<usize>,
+        shuffle: bool,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<String>, String> {
+        let shuffle = shuffle && !self.metadata.ordered;
+        if let (Some(count), Some(weights), true) = (count, &self.weights, shuffle) {
+            if self.words.is_empty() {
+                return Ok(Vec::new());
+            }
+            let index = WeightedIndex::new(weights)
+                .map_err(|_| "no word has a weight above zero".to_string())?;
+            return Ok((0..count)
+                .map(|_| self.words[index.sample(rng)].clone())
+                .collect());
+        }
+
+        let mut words: Vec<&String> = self.words.iter().collect();
+        if shuffle {
+            words.shuffle(rng);
+        }
+        let mut contents: Vec<String> = match count {
+            None => words.into_iter().cloned().collect(),
+            Some(count) => words.into_iter().cycle().take(count).cloned().collect(),
+        };
+        if shuffle {
+            contents.shuffle(rng);
+        }
+        Ok(contents)
+    }
+}
+
+/// Split a language file into its header, if it has one, and the word list.
+fn split_header(source: &str) -> Result<(Option<&str>, &str), String> {
+    let mut lines = source.split_inclusive('\n');
+    let start = match lines.next() {
+        Some(first) if first.trim_end() == HEADER_DELIMITER => first.len(),
+        _ => return Ok((None, source)),
+    };
+    let mut end = start;
+    for line in lines {
+        if line.trim_end() == HEADER_DELIMITER {
+            return Ok((Some(&source[start..end]), &source[end + line.len()..]));
+        }
+        end += line.len();
+    }
+    Err(format!(
+        "the header is never closed with a '{}' line",
+        HEADER_DELIMITER
+    ))
+}
+
+fn parse_header(header: &str) -> Result<Metadata, String> {
+    toml::from_str(header).map_err(|e| format!("invalid header: {}", e.message()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn plain_lists_have_no_header() {
+        let language = Language::parse("der\ndie\nund\n").unwrap();
+        assert_eq!(language.words, ["der", "die", "und"]);
+        assert!(language.metadata.description.is_none());
+        assert_eq!(Metadata::read("+\n").unwrap().summary(), "");
+    }
+
+    #[test]
+    fn reads_header() {
+        let source = "+++\nname = \"Hebrew\"\ndescription = \"Common words\"\n\
+                      direction = \"rtl\"\nordered = true\n+++\nשלום\nתודה\n";
+        let language = Language::parse(source).unwrap();
+        assert_eq!(language.words, ["שלום", "תודה"]);
+        assert!(language.metadata.ordered);
+        assert_eq!(
+            Metadata::read(source).unwrap().summary(),
+            "Hebrew: Common words (right-to-left)"
+        );
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let words = language.sample(Some(5), true, &mut rng).unwrap();
+        assert_eq!(words, ["שלום", "תודה", "שלום", "תודה", "שלום"]);
+    }
+
+    #[test]
+    fn reports_header_errors() {
+        assert!(Language::parse("+++\nname = \"x\"\n")
+            .unwrap_err()
+            .contains("never closed"));
+        assert!(Language::parse("+++\nshuffle = false\n+++\na\n")
+            .unwrap_err()
+            .contains("unknown field"));
+        assert!(Language::parse("+++\ndirection = \"up\"\n+++\na\n").is_err());
+    }
+
+    #[test]
+    fn samples_by_inline_weights() {
+        let language =
+            Language::parse("+++\nweights = \"inline\"\n+++\nund 1000\nrare word 0\n\nder 0.0\n")
+                .unwrap();
+        assert_eq!(language.words, ["und", "rare word", "der"]);
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let words = language.sample(Some(20), true, &mut rng).unwrap();
+        assert_eq!(words.len(), 20);
+        assert!(words.iter().all(|w| w == "und"));
+
+        // Without shuffling, weights can't apply and words come in order
+        let words = language.sample(Some(4), false, &mut rng).unwrap();
+        assert_eq!(words, ["und", "rare word", "der", "und"]);
+
+        let error = Language::parse("+++\nweights = \"inline\"\n+++\nund 1\nder\n").unwrap_err();
+        assert!(error.contains("line 5"), "{}", error);
+        assert!(Language::parse("+++\nweights = \"inline\"\n+++\nund -1\n").is_err());
+        let zero = Language::parse("+++\nweights = \"inline\"\n+++\nund 0\n").unwrap();
+        assert!(zero.sample(Some(3), true, &mut rng).is_err());
+    }
+
+    #[test]
+    fn parses_bands() {
+        assert_eq!(
+            "100..200".parse(),
+            Ok(Band {
+                first: 100,
+                last: Some(200)
+            })
+        );
+        assert_eq!(
+            "5..".parse(),
+            Ok(Band {
+                first: 5,
+                last: None
+            })
+        );
+        assert_eq!("1..1".parse::<Band>().unwrap().to_string(), "1..1");
+        for invalid in ["0..10", "20..10", "10", "a..b", "..10"] {
+            assert!(invalid.parse::<Band>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn selects_bands_and_lengths() {
+        let source = "+++\nweights = \"inline\"\n+++\nthe 9\nof 8\nand 7\nhouse 6\nthrough 5\n";
+        let mut language = Language::parse(source).unwrap();
+        language.select(Some("2..4".parse().unwrap()), None, None);
+        assert_eq!(language.words, ["of", "and", "house"]);
+        assert_eq!(language.weights, Some(vec![8.0, 7.0, 6.0]));
+
+        let mut language = Language::parse(source).unwrap();
+        language.select(Some(Band::top(4)), Some(3), Some(5));
+        assert_eq!(language.words, ["the", "and", "house"]);
+
+        let mut language = Language::parse("a\nbb\nccc\n").unwrap();
+        language.select(Some("2..".parse().unwrap()), None, Some(2));
+        assert_eq!(language.words, ["bb"]);
+    }
+
+    #[test]
+    fn rank_weights_favor_common_words() {
+        let mut source = String::from("+++\nweights = \"rank\"\n+++\n");
+        for i in 0..1000 {
+            source.push_str(&format!("word{}\n", i));
+        }
+        let language = Language::parse(&source).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let words = language.sample(Some(1000), true, &mut rng).unwrap();
+        let common = words.iter().filter(|w| *w == "word0").count();
+        let rare = words.iter().filter(|w| *w == "word999").count();
+        assert!(common > 50, "{}", common);
+        assert!(rare < 5, "{}", rare);
+
+        // Every word once, ignoring weights
+        let all = language.sample(None, true, &mut rng).unwrap();
+        assert_eq!(all.len(), 1000);
+    }
+}
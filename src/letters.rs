@@ -0,0 +1,97 @@
+//! Drills limited to a few letters, for learning a new keyboard layout, with more
+//! letters unlocked as results in the history get good enough.
+
+use crate::history::{option_value, record::Record, unescape_text};
+
+/// Results a test with the unlocked letters needs before the next letter unlocks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// Accuracy in percent
+    pub accuracy: f64,
+    /// Adjusted words per minute
+    pub wpm: f64,
+}
+
+/// Whether every character of `word` is one of `letters`, ignoring case.
+pub fn only_uses(word: &str, letters: &str) -> bool {
+    word.chars().all(|c| allows(letters, c))
+}
+
+/// Whether `c` is one of `letters`, ignoring case.
+pub fn allows(letters: &str, c: char) -> bool {
+    c.to_lowercase()
+        .all(|c| letters.chars().flat_map(char::to_lowercase).any(|l| l == c))
+}
+
+/// The letter set a history record was made with, if any.
+fn letters_of(record: &Record) -> Option<String> {
+    option_value(&record.options, "letters").map(unescape_text)
+}
+
+/// The letters unlocked so far: `start`, then each letter of `order` in turn, for as
+/// long as a test in `records` with the letters before it met the thresholds.
+pub fn unlocked(start: &str, order: &str, records: &[Record], thresholds: Thresholds) -> String {
+    let mut letters = start.to_owned();
+    for next in order.chars() {
+        if letters.contains(next) {
+            continue;
+        }
+        let passed = records.iter().any(|record| {
+            letters_of(record).as_deref() == Some(letters.as_str())
+                && record.accuracy >= thresholds.accuracy
+                && record.wpm_adjusted >= thresholds.wpm
+        });
+        if !passed {
+            break;
+        }
+        letters.push(next);
+    }
+    letters
+}
+
+/// The letters of `order` that are still locked.
+pub fn locked(unlocked: &str, order: &str) -> String {
+    order.chars().filter(|&c| !unlocked.contains(c)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: Thresholds = Thresholds {
+        accuracy: 95.0,
+        wpm: 30.0,
+    };
+
+    fn record(letters: &str, wpm: f64, accuracy: f64) -> Record {
+        Record::parse(&format!(
+            "2026-02-10 10:00:00,english,50,{wpm},{wpm},{accuracy},190,200,,,,,,,letters={letters}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn matches_words_made_of_the_letters() {
+        assert!(only_uses("stare", "arstneio"));
+        assert!(only_uses("Stare", "arstneio"));
+        assert!(!only_uses("start!", "arstneio"));
+        assert!(!only_uses("house", "arstneio"));
+    }
+
+    #[test]
+    fn unlocks_a_letter_per_passed_set() {
+        assert_eq!(unlocked("arst", "neio", &[], THRESHOLDS), "arst");
+
+        let records = [
+            record("arst", 35.0, 96.0),
+            // Too slow, so `i` stays locked
+            record("arstne", 20.0, 99.0),
+            record("arstn", 40.0, 97.0),
+        ];
+        assert_eq!(unlocked("arst", "neio", &records, THRESHOLDS), "arstne");
+        assert_eq!(locked("arstne", "neio"), "io");
+
+        let records = [record("arst", 50.0, 90.0)];
+        assert_eq!(unlocked("arst", "neio", &records, THRESHOLDS), "arst");
+    }
+}
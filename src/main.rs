@@ -1,53 +1,145 @@
+mod book;
+mod code;
 mod config;
 mod history;
+mod language;
+mod letters;
+mod markov;
 mod test;
 mod ui;
 
-use config::Config;
-use test::{results::Results, Test};
+use book::{Book, ChunkSize};
+use code::TestCode;
+use config::{Action, Config, Lookup, Profile, Screen};
+use language::{Band, Language, Metadata};
+use letters::Thresholds;
+use markov::Model;
+use test::{results::Results, Break, Test};
 
-use clap::Parser;
+use clap::{
+    error::ErrorKind, parser::ValueSource, ArgMatches, Args, CommandFactory, FromArgMatches,
+    Parser, Subcommand,
+};
 use crossterm::{
     self, cursor,
     event::{
-        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
-        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
     },
     execute, terminal,
 };
-use rand::{seq::SliceRandom, thread_rng};
-use ratatui::{backend::CrosstermBackend, terminal::Terminal};
+use rand::{seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use ratatui::{backend::CrosstermBackend, layout::Rect, terminal::Terminal};
 use rust_embed::RustEmbed;
 use std::{
+    cell::OnceCell,
+    collections::VecDeque,
+    env,
     ffi::OsString,
     fs,
-    io::{self, BufRead},
+    io::{self, BufRead, Read},
     num,
-    path::PathBuf,
+    path::{Path, PathBuf},
     str,
+    time::Duration,
 };
 
+/// How often the config and theme files are checked for changes during a test.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(RustEmbed)]
 #[folder = "resources/runtime"]
 struct Resources;
 
 #[derive(Debug, Parser)]
-#[command(about, version)]
+#[command(about, version, disable_help_subcommand = true)]
 struct Opt {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(short, long, global = true)]
+    debug: bool,
+
+    /// Use config file
+    #[arg(short, long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
+    /// Use the named theme instead of the configured one
+    #[arg(long, value_name = "NAME", global = true)]
+    theme: Option<String>,
+
+    #[command(flatten)]
+    test: TestArgs,
+
+    #[command(flatten)]
+    legacy: LegacyArgs,
+
+    /// Configuration, loaded on first use
+    #[arg(skip)]
+    loaded_config: OnceCell<Config>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    /// Run a typing test (the default when no command is given)
+    Test(Box<TestArgs>),
+    /// Show past results, or manage the results history
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+
+        #[command(flatten)]
+        list: ListArgs,
+    },
+    /// Show aggregated statistics
+    Stats(StatsArgs),
+    /// List installed languages
+    Languages,
+    /// Show where configuration, languages and history are stored, or manage the config file
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum ConfigAction {
+    /// Write a commented default config.toml
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Validate the config file and report problems
+    Check,
+    /// Print the effective configuration, with defaults filled in
+    Show,
+}
+
+#[derive(Debug, Clone, Args)]
+struct TestArgs {
     /// Read test contents from the specified file, or "-" for stdin
     #[arg(value_name = "PATH")]
     contents: Option<PathBuf>,
 
-    #[arg(short, long)]
-    debug: bool,
+    /// Type the file as a book: keep its lines and paragraphs, take --words words at a
+    /// time, and continue where the last test stopped
+    #[arg(long, requires = "contents", conflicts_with_all = ["pseudo", "letters"])]
+    book: bool,
 
-    /// Specify word count
-    #[arg(short, long, value_name = "N", default_value = "50")]
-    words: num::NonZeroUsize,
+    /// Take N sentences at a time from the --book instead of --words words
+    #[arg(long, value_name = "N", requires = "book")]
+    sentences: Option<num::NonZeroUsize>,
 
-    /// Use config file
-    #[arg(short, long, value_name = "PATH")]
-    config: Option<PathBuf>,
+    /// Use test options from a [profile.<NAME>] table in the config file
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Specify word count [default: 50]
+    #[arg(short, long, value_name = "N")]
+    words: Option<num::NonZeroUsize>,
 
     /// Specify test language in file
     #[arg(long, value_name = "PATH")]
@@ -57,69 +149,675 @@ struct Opt {
     #[arg(short, long, value_name = "LANG")]
     language: Option<String>,
 
-    /// List installed languages
+    /// Use only the N most common words of the language
+    #[arg(long, value_name = "N", conflicts_with_all = ["range", "contents"])]
+    top: Option<num::NonZeroUsize>,
+
+    /// Use only words ranked A to B in the language, counting from 1 (e.g., 500..1000 or 500..)
+    #[arg(long, value_name = "A..B", conflicts_with = "contents")]
+    range: Option<Band>,
+
+    /// Use only words with at least N characters
+    #[arg(long, value_name = "N", conflicts_with = "contents")]
+    min_len: Option<usize>,
+
+    /// Use only words with at most N characters
+    #[arg(long, value_name = "N", conflicts_with = "contents")]
+    max_len: Option<usize>,
+
+    /// Type pseudo-words made from the letter patterns of the language or file
     #[arg(long)]
-    list_languages: bool,
+    pseudo: bool,
+
+    /// Letters in each n-gram of the --pseudo model [default: 3]
+    #[arg(long, value_name = "N", requires = "pseudo", value_parser = clap::value_parser!(u8).range(1..=8))]
+    order: Option<u8>,
+
+    /// Make these letters more common in --pseudo words
+    #[arg(long, value_name = "LETTERS", requires = "pseudo")]
+    bias: Option<String>,
+
+    /// Seed for shuffling and generating words, to get the same test again
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// Take the test described by a test code from the results screen
+    #[arg(long, value_name = "CODE", conflicts_with_all = [
+        "contents", "profile", "words", "language_file", "language", "top", "range",
+        "min_len", "max_len", "pseudo", "order", "bias", "seed", "letters", "unlock",
+    ])]
+    code: Option<TestCode>,
+
+    /// Take today's daily challenge, the same test for everyone on a calendar date
+    #[arg(long, conflicts_with_all = [
+        "contents", "profile", "words", "language_file", "language", "top", "range",
+        "min_len", "max_len", "pseudo", "order", "bias", "seed", "letters", "unlock", "code",
+    ])]
+    daily: bool,
+
+    /// Date of the daily challenge being taken
+    #[arg(skip)]
+    daily_date: Option<String>,
+
+    /// Use only words made of these characters (e.g., "arstneio")
+    #[arg(long, value_name = "SET")]
+    letters: Option<String>,
+
+    /// Unlock these letters one at a time after --letters, each once a test with the
+    /// letters before it reaches --unlock-accuracy and --unlock-wpm
+    #[arg(long, value_name = "ORDER", requires = "letters")]
+    unlock: Option<String>,
+
+    /// Accuracy in percent a test needs to unlock the next letter [default: 95]
+    #[arg(long, value_name = "PCT", requires = "unlock")]
+    unlock_accuracy: Option<f64>,
+
+    /// Adjusted WPM a test needs to unlock the next letter [default: 30]
+    #[arg(long, value_name = "WPM", requires = "unlock")]
+    unlock_wpm: Option<f64>,
 
     /// Disable backtracking to completed words
-    #[arg(long)]
+    #[arg(long, overrides_with = "backtrack")]
     no_backtrack: bool,
 
+    /// Allow backtracking to completed words (overrides --no-backtrack from the config)
+    #[arg(long, overrides_with = "no_backtrack")]
+    backtrack: bool,
+
     /// Enable sudden death mode to restart on first error
-    #[arg(long)]
+    #[arg(long, overrides_with = "no_sudden_death")]
     sudden_death: bool,
 
+    /// Disable sudden death mode (overrides --sudden-death from the config)
+    #[arg(long, overrides_with = "sudden_death")]
+    no_sudden_death: bool,
+
     /// Ignore case when comparing typed input
-    #[arg(long)]
+    #[arg(long, overrides_with = "case_sensitive")]
     case_insensitive: bool,
 
+    /// Compare case of typed input (overrides --case-insensitive from the config)
+    #[arg(long, overrides_with = "case_insensitive")]
+    case_sensitive: bool,
+
+    /// Accept base letters for letters with diacritics, like "e" for "é"
+    #[arg(long, overrides_with = "match_diacritics")]
+    ignore_diacritics: bool,
+
+    /// Compare diacritics of typed input (overrides --ignore-diacritics from the config)
+    #[arg(long, overrides_with = "ignore_diacritics")]
+    match_diacritics: bool,
+
     /// Disable backspace/delete during test
-    #[arg(long)]
+    #[arg(long, overrides_with = "backspace")]
     no_backspace: bool,
 
+    /// Allow backspace/delete during test (overrides --no-backspace from the config)
+    #[arg(long, overrides_with = "no_backspace")]
+    backspace: bool,
+
     /// Don't shuffle word order
-    #[arg(long)]
+    #[arg(long, overrides_with = "shuffle")]
     no_shuffle: bool,
 
+    /// Shuffle word order (overrides --no-shuffle from the config)
+    #[arg(long, overrides_with = "no_shuffle")]
+    shuffle: bool,
+
     /// Use entire word list (ignore --words limit)
-    #[arg(long)]
+    #[arg(long, overrides_with = "limit")]
     no_limit: bool,
 
+    /// Limit the word list to --words (overrides --no-limit from the config)
+    #[arg(long, overrides_with = "no_limit")]
+    limit: bool,
+
     /// Show only the next N upcoming words (past and current word always visible)
-    #[arg(long, value_name = "N")]
+    #[arg(long, value_name = "N", overrides_with = "no_look_ahead")]
     look_ahead: Option<usize>,
 
-    /// Show history of past results
-    #[arg(long)]
+    /// Show all upcoming words (overrides --look-ahead from the config)
+    #[arg(long, overrides_with = "look_ahead")]
+    no_look_ahead: bool,
+
+    /// Disable saving results to history
+    #[arg(long, overrides_with = "save")]
+    no_save: bool,
+
+    /// Save results to history (overrides --no-save from the config)
+    #[arg(long, overrides_with = "no_save")]
+    save: bool,
+}
+
+/// Flags from before subcommands existed. Hidden from help but still accepted,
+/// so `ttyper --history --stats` keeps working as `ttyper stats`.
+#[derive(Debug, Clone, Args)]
+struct LegacyArgs {
+    #[arg(long, hide = true)]
+    list_languages: bool,
+
+    #[arg(long, hide = true)]
     history: bool,
 
-    /// Show only the last N history entries
-    #[arg(long, value_name = "N")]
+    #[arg(long, hide = true, value_name = "N", requires = "history")]
     last: Option<usize>,
 
-    /// Filter history by language
-    #[arg(long, value_name = "LANG")]
+    #[arg(long, hide = true, value_name = "LANG", requires = "history")]
     history_lang: Option<String>,
 
-    /// Filter history from date (YYYY-MM-DD)
-    #[arg(long, value_name = "DATE")]
+    #[arg(long, hide = true, value_name = "DATE", value_parser = date_arg, requires = "history")]
     since: Option<String>,
 
-    /// Filter history until date (YYYY-MM-DD)
-    #[arg(long, value_name = "DATE")]
+    #[arg(long, hide = true, value_name = "DATE", value_parser = date_arg, requires = "history")]
     until: Option<String>,
 
-    /// Show aggregated statistics instead of raw history
-    #[arg(long)]
+    #[arg(long, hide = true, value_name = "TAG", requires = "history")]
+    tag: Option<String>,
+
+    #[arg(long, hide = true, requires = "history")]
     stats: bool,
 
-    /// Disable saving results to history
+    #[arg(
+        long,
+        hide = true,
+        value_name = "KEY",
+        requires = "stats",
+        conflicts_with = "keys"
+    )]
+    group_by: Option<history::GroupBy>,
+
+    #[arg(long, hide = true, requires = "stats")]
+    keys: bool,
+}
+
+/// Filters shared by the history listing, statistics and deletion.
+#[derive(Debug, Clone, Args)]
+struct FilterArgs {
+    /// Only include results in this language
+    #[arg(long, value_name = "LANG", alias = "history-lang")]
+    lang: Option<String>,
+
+    /// Only include results from this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = date_arg)]
+    since: Option<String>,
+
+    /// Only include results until this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = date_arg)]
+    until: Option<String>,
+
+    /// Only include results with this tag
+    #[arg(long, value_name = "TAG")]
+    tag: Option<String>,
+}
+
+impl FilterArgs {
+    /// Borrow as history filters, rejecting empty date ranges.
+    fn filters(&self) -> Result<history::Filters<'_>, String> {
+        if let (Some(since), Some(until)) = (&self.since, &self.until) {
+            if since > until {
+                return Err(
+                    "Error: --since date must be before or equal to --until date".to_string(),
+                );
+            }
+        }
+        Ok(history::Filters {
+            language: self.lang.as_deref(),
+            since: self.since.as_deref(),
+            until: self.until.as_deref(),
+            tag: self.tag.as_deref(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+struct ListArgs {
+    /// Show only the last N entries
+    #[arg(long, value_name = "N")]
+    last: Option<usize>,
+
+    #[command(flatten)]
+    filters: FilterArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+struct StatsArgs {
+    #[command(flatten)]
+    filters: FilterArgs,
+
+    /// Compare statistics side by side, grouped by language, word count, week or month
+    #[arg(long, value_name = "KEY", conflicts_with = "keys")]
+    group_by: Option<history::GroupBy>,
+
+    /// Show lifetime per-key accuracy and speed
     #[arg(long)]
-    no_save: bool,
+    keys: bool,
+
+    /// Show the daily challenge streak and score trend
+    #[arg(long, conflicts_with_all = ["group_by", "keys"])]
+    daily: bool,
 }
 
-impl Opt {
-    fn gen_contents(&self) -> Result<Vec<String>, String> {
+/// The daily challenge for `date`: the same language, length and options for everyone,
+/// with the date as the seed.
+fn daily_code(date: chrono::NaiveDate) -> TestCode {
+    TestCode {
+        language: DAILY_LANGUAGE.to_string(),
+        words: DAILY_WORDS,
+        seed: date.format("%Y%m%d").to_string().parse().unwrap(),
+        flags: String::new(),
+        options: String::new(),
+    }
+}
+
+/// Clap value parser for YYYY-MM-DD dates.
+fn date_arg(date: &str) -> Result<String, String> {
+    history::validate_date_format(date)
+        .map(|()| date.to_string())
+        .map_err(|_| "expected YYYY-MM-DD (e.g., 2026-02-14)".to_string())
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum HistoryAction {
+    /// Export history as CSV or JSON
+    Export {
+        /// Output format
+        #[arg(long, value_name = "FORMAT", default_value = "csv")]
+        format: history::interop::ExportFormat,
+
+        /// Write to the specified file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Merge entries from a ttyper or Monkeytype export into history, skipping duplicates
+    Import {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+    },
+    /// Check the history file for malformed rows
+    Verify,
+    /// Fix malformed rows in the history file, keeping a backup of the original
+    Repair,
+    /// Delete entries by ID or datetime (as shown by `ttyper history`) or by filter
+    Delete {
+        #[arg(value_name = "ID", conflicts_with_all = ["lang", "since", "until", "tag"])]
+        ids: Vec<history::edit::EntryRef>,
+
+        #[command(flatten)]
+        filters: FilterArgs,
+
+        /// List matching entries without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Add or remove tags on entries
+    Tag {
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<history::edit::EntryRef>,
+
+        /// Tag to add (repeatable)
+        #[arg(short, long, value_name = "TAG")]
+        add: Vec<String>,
+
+        /// Tag to remove (repeatable)
+        #[arg(short, long, value_name = "TAG")]
+        remove: Vec<String>,
+    },
+    /// Attach a note to an entry (an empty note removes it)
+    Annotate {
+        #[arg(value_name = "ID")]
+        id: history::edit::EntryRef,
+
+        #[arg(value_name = "NOTE")]
+        note: String,
+    },
+    /// Delete entries recorded before a date
+    Prune {
+        /// Delete entries before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = date_arg)]
+        before: String,
+
+        /// List matching entries without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Word count when neither the command line nor the config sets one.
+const DEFAULT_WORDS: usize = 50;
+/// Letters per n-gram of the --pseudo model
+const DEFAULT_ORDER: u8 = 3;
+/// Language and word count of the daily challenge
+const DAILY_LANGUAGE: &str = "english1000";
+const DAILY_WORDS: usize = 50;
+/// Results a test needs to unlock the next --unlock letter
+const DEFAULT_UNLOCK: Thresholds = Thresholds {
+    accuracy: 95.0,
+    wpm: 30.0,
+};
+
+impl TestArgs {
+    /// Fill in options not given on the command line from the selected profile, then
+    /// from the `[defaults]` table of the config.
+    fn apply_config(&mut self, config: &Config) -> Result<(), String> {
+        let profile = match &self.profile {
+            Some(name) => config.profile(name)?.clone(),
+            None => Profile::default(),
+        };
+        let defaults = &config.defaults;
+
+        self.words = self.words.or(profile.words).or(defaults.words);
+        // Either language option on the command line replaces both from the config,
+        // and either one in the profile replaces both from the defaults
+        if self.language.is_none() && self.language_file.is_none() {
+            let from = if profile.language.is_some() || profile.language_file.is_some() {
+                &profile
+            } else {
+                defaults
+            };
+            self.language = from.language.clone();
+            self.language_file = from.language_file.clone();
+        }
+
+        // A flag or its negation on the command line wins over the profile, and the
+        // profile over the defaults
+        let resolve = |set: bool, unset: bool, option: fn(&Profile) -> Option<bool>| {
+            set || (!unset && option(&profile).or(option(defaults)).unwrap_or(false))
+        };
+        self.no_backtrack = resolve(self.no_backtrack, self.backtrack, |p| p.no_backtrack);
+        self.sudden_death = resolve(self.sudden_death, self.no_sudden_death, |p| p.sudden_death);
+        self.case_insensitive = resolve(self.case_insensitive, self.case_sensitive, |p| {
+            p.case_insensitive
+        });
+        self.ignore_diacritics = resolve(self.ignore_diacritics, self.match_diacritics, |p| {
+            p.ignore_diacritics
+        });
+        self.no_backspace = resolve(self.no_backspace, self.backspace, |p| p.no_backspace);
+        self.no_shuffle = resolve(self.no_shuffle, self.shuffle, |p| p.no_shuffle);
+        self.no_limit = resolve(self.no_limit, self.limit, |p| p.no_limit);
+        self.no_save = resolve(self.no_save, self.save, |p| p.no_save);
+        if !self.no_look_ahead {
+            self.look_ahead = self
+                .look_ahead
+                .or(profile.look_ahead)
+                .or(defaults.look_ahead);
+        }
+        if let Some(code) = self.code.clone() {
+            self.apply_code(&code)?;
+        }
+        if self.daily {
+            let today = chrono::Local::now().date_naive();
+            self.apply_code(&daily_code(today))?;
+            self.daily_date = Some(today.to_string());
+        }
+        Ok(())
+    }
+
+    /// Set every option that decides the words and how the test plays from `code`.
+    fn apply_code(&mut self, code: &TestCode) -> Result<(), String> {
+        self.language = Some(code.language.clone());
+        self.language_file = None;
+        self.words = num::NonZeroUsize::new(code.words);
+        self.seed = Some(code.seed);
+        self.no_backtrack = code.flag('b');
+        self.sudden_death = code.flag('d');
+        self.case_insensitive = code.flag('i');
+        self.no_backspace = code.flag('k');
+        self.no_shuffle = code.flag('s');
+        self.no_limit = code.flag('l');
+        self.ignore_diacritics = code.flag('a');
+
+        for (key, value) in code.options() {
+            let invalid = || format!("Error: Invalid option '{}={}' in test code.", key, value);
+            match key {
+                "top" => self.top = Some(value.parse().map_err(|_| invalid())?),
+                "range" => self.range = Some(value.parse().map_err(|_| invalid())?),
+                "min_len" => self.min_len = Some(value.parse().map_err(|_| invalid())?),
+                "max_len" => self.max_len = Some(value.parse().map_err(|_| invalid())?),
+                "pseudo" => {
+                    self.pseudo = true;
+                    self.order = Some(value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?);
+                }
+                "bias" => self.bias = Some(history::unescape_text(value)),
+                "letters" => self.letters = Some(history::unescape_text(value)),
+                _ => {
+                    return Err(format!(
+                        "Error: Unknown option '{}' in test code. It may be from a newer version of ttyper.",
+                        key
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Seed for the next test: --seed, or a new random one.
+    fn next_seed(&self) -> u64 {
+        self.seed
+            .unwrap_or_else(|| thread_rng().gen::<u32>().into())
+    }
+
+    /// Code to take the test made with `seed` and `letters` again, if it's from a named
+    /// language.
+    fn test_code(&self, opt: &Opt, seed: u64, letters: Option<&str>) -> Option<TestCode> {
+        if self.contents.is_some() || self.language_file.is_some() {
+            return None;
+        }
+        let flags = [
+            self.no_backtrack,
+            self.sudden_death,
+            self.case_insensitive,
+            self.no_backspace,
+            self.no_shuffle,
+            self.no_limit,
+            self.ignore_diacritics,
+        ];
+        Some(TestCode {
+            language: self.effective_language(opt),
+            words: self.words(),
+            seed,
+            flags: code::FLAGS
+                .chars()
+                .zip(flags)
+                .filter_map(|(letter, on)| on.then_some(letter))
+                .collect(),
+            options: self.word_options(letters).join(";"),
+        })
+    }
+
+    /// Number of words per test
+    fn words(&self) -> usize {
+        self.words.map_or(DEFAULT_WORDS, num::NonZeroUsize::get)
+    }
+
+    /// Rank band of the language to draw words from
+    fn band(&self) -> Option<Band> {
+        self.top.map(|n| Band::top(n.get())).or(self.range)
+    }
+
+    /// Letters the words may use: --letters, plus those unlocked so far by --unlock.
+    /// This reads the history, so it's worked out once per test.
+    fn letter_set(&self, opt: &Opt) -> Option<String> {
+        let start = self.letters.as_ref()?;
+        let Some(order) = &self.unlock else {
+            return Some(start.clone());
+        };
+        let records = opt
+            .history_file()
+            .ok()
+            .and_then(|file| history::interop::read_records(&file).ok())
+            .unwrap_or_default();
+        Some(letters::unlocked(start, order, &records, self.thresholds()))
+    }
+
+    fn thresholds(&self) -> Thresholds {
+        Thresholds {
+            accuracy: self.unlock_accuracy.unwrap_or(DEFAULT_UNLOCK.accuracy),
+            wpm: self.unlock_wpm.unwrap_or(DEFAULT_UNLOCK.wpm),
+        }
+    }
+
+    /// Which letters --unlock has unlocked, and what the next one needs.
+    fn unlock_progress(&self, opt: &Opt) -> Option<String> {
+        let order = self.unlock.as_ref()?;
+        let unlocked = self.letter_set(opt)?;
+        let thresholds = self.thresholds();
+        Some(match letters::locked(&unlocked, order).chars().next() {
+            Some(next) => format!(
+                "Letters: {}. '{}' unlocks after a test with these letters at {}% accuracy and {} WPM.",
+                unlocked, next, thresholds.accuracy, thresholds.wpm
+            ),
+            None => format!("Letters: {}. All letters are unlocked.", unlocked),
+        })
+    }
+
+    /// How the words were chosen, for history: `;`-separated `key=value` pairs,
+    /// ending with the seed if the test has one. Practice rounds are only marked as
+    /// such, since the options didn't choose their words.
+    fn options(&self, seed: Option<u64>, letters: Option<&str>, practice: bool) -> String {
+        if practice {
+            return "practice".to_string();
+        }
+        let mut options = self.word_options(letters);
+        if let Some(seed) = seed {
+            options.push(format!("seed={}", seed));
+        }
+        if let Some(date) = &self.daily_date {
+            options.push(format!("daily={}", date));
+        }
+        options.join(";")
+    }
+
+    /// The options that decide which words a test has, as `key=value` pairs. `letters`
+    /// is the letter set the words were chosen with.
+    fn word_options(&self, letters: Option<&str>) -> Vec<String> {
+        let mut options = Vec::new();
+        if let Some(top) = self.top {
+            options.push(format!("top={}", top));
+        }
+        if let Some(range) = self.range {
+            options.push(format!("range={}", range));
+        }
+        if let Some(min_len) = self.min_len {
+            options.push(format!("min_len={}", min_len));
+        }
+        if let Some(max_len) = self.max_len {
+            options.push(format!("max_len={}", max_len));
+        }
+        if self.pseudo {
+            options.push(format!("pseudo={}", self.order.unwrap_or(DEFAULT_ORDER)));
+        }
+        if let Some(letters) = &self.bias {
+            options.push(format!("bias={}", history::escape_text(letters)));
+        }
+        if let Some(letters) = letters {
+            options.push(format!("letters={}", history::escape_text(letters)));
+        }
+        options
+    }
+
+    /// Generate --pseudo words from a model of `words` and their weights, using only
+    /// `letters` if given.
+    fn pseudo_words<'a>(
+        &self,
+        words: impl IntoIterator<Item = (&'a str, f64)>,
+        letters: Option<&str>,
+        rng: &mut impl Rng,
+    ) -> Vec<String> {
+        let mut model = Model::train(words, self.order.unwrap_or(DEFAULT_ORDER).into());
+        if let Some(letters) = &self.bias {
+            model.bias(letters);
+        }
+        if let Some(letters) = letters {
+            model.restrict(|c| letters::allows(letters, c));
+        }
+        model.words(self.words(), rng)
+    }
+
+    /// Words for a test, and where the prompt breaks between them: the next chunk for
+    /// --book, otherwise words from [`Self::gen_contents`].
+    fn next_contents(&self, opt: &Opt, seed: u64) -> Result<Contents, String> {
+        match &self.contents {
+            Some(path) if self.book => self.book_chunk(opt, path),
+            _ => {
+                let letters = self.letter_set(opt);
+                Ok(Contents {
+                    words: self.gen_contents(opt, seed, letters.as_deref())?,
+                    breaks: Vec::new(),
+                    book_start: None,
+                    letters,
+                })
+            }
+        }
+    }
+
+    /// The next chunk of the --book at `path`, starting at its bookmark. A book read to
+    /// the end starts over.
+    fn book_chunk(&self, opt: &Opt, path: &Path) -> Result<Contents, String> {
+        let stdin = path.as_os_str() == "-";
+        let mut text = String::new();
+        if stdin {
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Error: Cannot read stdin: {}", e))?;
+        } else {
+            text = fs::read_to_string(path)
+                .map_err(|e| format!("Error: Cannot read '{}': {}", path.display(), e))?;
+        }
+        let markdown = path
+            .extension()
+            .is_some_and(|ext| ext == "md" || ext == "markdown");
+        let book = Book::parse(&text, markdown);
+
+        let bookmarks_file = opt.bookmarks_file().filter(|_| !stdin);
+        let mut start = bookmarks_file
+            .as_ref()
+            .map_or(0, |file| book::bookmark(file, path));
+        if start >= book.words.len() {
+            start = 0;
+        }
+        let size = match self.sentences {
+            Some(n) => ChunkSize::Sentences(n.get()),
+            None if self.no_limit => ChunkSize::Words(book.words.len()),
+            None => ChunkSize::Words(self.words()),
+        };
+        let (words, breaks) = book.slice(book.chunk(start, size));
+        Ok(Contents {
+            words,
+            breaks,
+            book_start: bookmarks_file.map(|_| start),
+            letters: None,
+        })
+    }
+
+    /// Remember where the next chunk of the --book starts: after the last word typed
+    /// in `test`, which started at word `start`.
+    fn save_bookmark(&self, opt: &Opt, test: &Test, start: usize, results: &mut Results) {
+        let (Some(path), Some(file)) = (&self.contents, opt.bookmarks_file()) else {
+            return;
+        };
+        let typed = if test.complete {
+            test.words.len()
+        } else {
+            test.current_word
+        };
+        if let Err(e) = book::set_bookmark(&file, path, start + typed) {
+            results.save_error = Some(format!(
+                "Failed to save bookmark to {}: {}",
+                file.display(),
+                e
+            ));
+        }
+    }
+
+    /// Words for a test, using only `letters` if given. The same `seed` and options give
+    /// the same words.
+    fn gen_contents(
+        &self,
+        opt: &Opt,
+        seed: u64,
+        letters: Option<&str>,
+    ) -> Result<Vec<String>, String> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
         match &self.contents {
             Some(path) => {
                 let lines: Vec<String> = if path.as_os_str() == "-" {
@@ -137,16 +835,27 @@ impl Opt {
                         .collect()
                 };
 
-                Ok(lines
+                let words: Vec<String> = lines
                     .iter()
                     .flat_map(|line| line.split_whitespace().map(String::from))
-                    .collect())
+                    .collect();
+                if self.pseudo {
+                    let words = words.iter().map(|w| (w.as_str(), 1.0));
+                    return Ok(self.pseudo_words(words, letters, &mut rng));
+                }
+                Ok(match letters {
+                    Some(letters) => words
+                        .into_iter()
+                        .filter(|w| letters::only_uses(w, letters))
+                        .collect(),
+                    None => words,
+                })
             }
             None => {
                 let lang_name = self
                     .language
                     .clone()
-                    .unwrap_or_else(|| self.config().default_language);
+                    .unwrap_or_else(|| opt.config().default_language.clone());
 
                 let bytes: Vec<u8> = if let Some(lang_file) = &self.language_file {
                     fs::read(lang_file).map_err(|e| {
@@ -157,83 +866,267 @@ impl Opt {
                         )
                     })?
                 } else {
-                    fs::read(self.language_dir().join(&lang_name))
-                        .ok()
-                        .or_else(|| {
-                            Resources::get(&format!("language/{}", &lang_name))
-                                .map(|f| f.data.into_owned())
-                        })
-                        .ok_or_else(|| {
+                    opt.language_source(&lang_name).ok_or_else(|| {
                             format!(
-                                "Error: Language '{}' not found. Use --list-languages to see available languages.",
+                                "Error: Language '{}' not found. Run `ttyper languages` to see available languages.",
                                 lang_name
                             )
                         })?
                 };
 
-                let mut rng = thread_rng();
+                let source = str::from_utf8(&bytes).map_err(|_| {
+                    if let Some(lang_file) = &self.language_file {
+                        format!(
+                            "Error: Language file '{}' has invalid UTF-8 encoding.",
+                            lang_file.display()
+                        )
+                    } else {
+                        format!(
+                            "Error: Language '{}' has invalid UTF-8 encoding.",
+                            lang_name
+                        )
+                    }
+                })?;
+                let in_language = |e: String| match &self.language_file {
+                    Some(lang_file) => {
+                        format!("Error: In language file '{}': {}", lang_file.display(), e)
+                    }
+                    None => format!("Error: In language '{}': {}", lang_name, e),
+                };
 
-                let mut language: Vec<&str> = str::from_utf8(&bytes)
-                    .map_err(|_| {
-                        if let Some(lang_file) = &self.language_file {
-                            format!(
-                                "Error: Language file '{}' has invalid UTF-8 encoding.",
-                                lang_file.display()
-                            )
-                        } else {
-                            format!(
-                                "Error: Language '{}' has invalid UTF-8 encoding.",
-                                lang_name
-                            )
-                        }
-                    })?
-                    .lines()
-                    .collect();
-                if !self.no_shuffle {
-                    language.shuffle(&mut rng);
+                let mut language = Language::parse(source).map_err(in_language)?;
+                let available = language.words.len();
+                language.select(self.band(), self.min_len, self.max_len);
+                if available > 0 && language.words.is_empty() {
+                    return Err(format!(
+                        "Error: No words of language '{}' are left after --top, --range, --min-len and --max-len.",
+                        lang_name
+                    ));
+                }
+                if self.pseudo {
+                    let words = language.weighted_words();
+                    return Ok(self.pseudo_words(words, letters, &mut rng));
                 }
+                if let Some(letters) = letters {
+                    language.retain(|_, word| letters::only_uses(word, letters));
+                    if language.words.is_empty() {
+                        return Err(format!(
+                            "Error: No words of language '{}' use only the letters '{}'. Add --pseudo to make some up.",
+                            lang_name, letters
+                        ));
+                    }
+                }
+                let count = (!self.no_limit).then(|| self.words());
+                language
+                    .sample(count, !self.no_shuffle, &mut rng)
+                    .map_err(in_language)
+            }
+        }
+    }
+
+    /// Start a test on `contents` with these options.
+    fn new_test(&self, contents: Vec<String>, config: &Config) -> Test {
+        Test::new(
+            contents,
+            !self.no_backtrack,
+            self.sudden_death,
+            self.case_insensitive,
+            self.no_backspace,
+            self.look_ahead,
+        )
+        .with_keys(config.key_map.edit_keys())
+        .with_ignore_diacritics(self.ignore_diacritics)
+        .with_ascii_dead_keys(config.ascii_dead_keys)
+        .with_substitutions(&config.substitutions)
+    }
+
+    /// Results of a finished test, saved to history. `seed` is the seed the words were
+    /// made with, if they were, `book_start` the word of the --book they start at,
+    /// `letters` the letter set they were chosen with and `practice` whether the test
+    /// practised words of the one before.
+    fn results(
+        &self,
+        opt: &Opt,
+        test: &Test,
+        seed: Option<u64>,
+        book_start: Option<usize>,
+        letters: Option<&str>,
+        practice: bool,
+    ) -> Results {
+        let mut results = Results::from(test);
+        results.code = seed
+            .and_then(|seed| self.test_code(opt, seed, letters))
+            .map(|code| code.to_string());
+        self.save_results(opt, &mut results, seed, letters, practice);
+        if let Some(start) = book_start {
+            self.save_bookmark(opt, test, start, &mut results);
+        }
+        results
+    }
+
+    /// Save results to history unless disabled. `seed`, `letters` and `practice` are
+    /// as for [`Self::results`]. Failures are recorded on the results so they can be
+    /// shown on the results screen instead of being lost.
+    fn save_results(
+        &self,
+        opt: &Opt,
+        results: &mut Results,
+        seed: Option<u64>,
+        letters: Option<&str>,
+        practice: bool,
+    ) {
+        if self.no_save {
+            return;
+        }
+        let history_file = match opt.history_file() {
+            Ok(history_file) => history_file,
+            Err(e) => {
+                results.save_error = Some(e);
+                return;
+            }
+        };
+        match history::save_results(
+            &history_file,
+            &self.effective_language(opt),
+            self.words(),
+            &self.options(seed, letters, practice),
+            results,
+        ) {
+            Ok(true) => {}
+            Ok(false) => {
+                results.save_error = Some(format!(
+                    "Not saved: only the first attempt at the {} challenge counts.",
+                    self.daily_date.as_deref().unwrap_or_default()
+                ))
+            }
+            Err(e) => {
+                results.save_error = Some(format!(
+                    "Failed to save history to {}: {}",
+                    history_file.display(),
+                    e
+                ))
+            }
+        }
+    }
+
+    /// Get the effective language name for history logging
+    fn effective_language(&self, opt: &Opt) -> String {
+        self.language
+            .clone()
+            .unwrap_or_else(|| opt.config().default_language.clone())
+    }
+}
+
+impl Opt {
+    /// Parse the command line. Unlike plain `Opt::parse`, this rejects options given
+    /// next to a subcommand they don't belong to, e.g. `ttyper -w 10 history`.
+    fn parse_args() -> Self {
+        let mut cmd = Self::command();
+        let matches = cmd.get_matches_mut();
+        if let Some(arg) = misplaced_arg(&cmd, &matches) {
+            cmd.error(
+                ErrorKind::ArgumentConflict,
+                format!("'{}' cannot be used with a subcommand", arg),
+            )
+            .exit();
+        }
+        Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+
+    /// The command to run. Without a subcommand this is a test, unless one of the
+    /// legacy flags selects another command.
+    fn selected_command(&self) -> Command {
+        if let Some(command) = &self.command {
+            return command.clone();
+        }
+
+        let legacy = &self.legacy;
+        let filters = FilterArgs {
+            lang: legacy.history_lang.clone(),
+            since: legacy.since.clone(),
+            until: legacy.until.clone(),
+            tag: legacy.tag.clone(),
+        };
+        if legacy.list_languages {
+            Command::Languages
+        } else if legacy.stats {
+            Command::Stats(StatsArgs {
+                filters,
+                group_by: legacy.group_by,
+                keys: legacy.keys,
+                daily: self.test.daily,
+            })
+        } else if legacy.history {
+            Command::History {
+                action: None,
+                list: ListArgs {
+                    last: legacy.last,
+                    filters,
+                },
+            }
+        } else {
+            Command::Test(Box::new(self.test.clone()))
+        }
+    }
+
+    /// Config file path (`--config`, or config.toml in the config directory)
+    fn config_file(&self) -> Result<PathBuf, String> {
+        match &self.config {
+            Some(config) => Ok(config.clone()),
+            None => self
+                .config_dir()
+                .map(|dir| dir.join("config.toml"))
+                .ok_or_else(|| {
+                    "Error: No config directory found. Set TTYPER_CONFIG_DIR or use --config."
+                        .to_string()
+                }),
+        }
+    }
+
+    /// Configuration. Loaded once, so errors in the file are only reported once.
+    fn config(&self) -> &Config {
+        self.loaded_config.get_or_init(|| self.load_config())
+    }
 
-                let mut contents: Vec<_> = if self.no_limit {
-                    language.into_iter().map(ToOwned::to_owned).collect()
-                } else {
-                    language
-                        .into_iter()
-                        .cycle()
-                        .take(self.words.get())
-                        .map(ToOwned::to_owned)
-                        .collect()
-                };
-                if !self.no_shuffle {
-                    contents.shuffle(&mut rng);
-                }
+    /// Read and parse the config file. Without one, the defaults are used.
+    fn read_config(&self) -> Result<Config, toml::de::Error> {
+        match self.config_file().ok().and_then(|file| fs::read(file).ok()) {
+            Some(bytes) => Config::parse(str::from_utf8(&bytes).unwrap_or_default()),
+            None => Ok(Config::default()),
+        }
+    }
 
-                Ok(contents)
-            }
+    fn load_config(&self) -> Config {
+        let mut config = self.read_config().unwrap_or_else(|e| {
+            eprintln!("Error in config.toml: {}", e);
+            eprintln!("Using default configuration.");
+            Config::default()
+        });
+        if let Err(e) = config.resolve_theme(self.theme.as_deref(), self) {
+            eprintln!("Error: {}", e);
+            eprintln!("Using default theme.");
         }
+        config
     }
 
-    /// Configuration
-    fn config(&self) -> Config {
-        fs::read(
-            self.config
-                .clone()
-                .unwrap_or_else(|| self.config_dir().join("config.toml")),
-        )
-        .map(|bytes| {
-            let s = str::from_utf8(&bytes).unwrap_or_default();
-            match toml::from_str(s) {
-                Ok(config) => config,
-                Err(e) => {
-                    eprintln!("Error in config.toml: {}", e);
-                    eprintln!("Using default configuration.");
-                    Config::default()
-                }
+    /// Read the config again after it changed, for a test that's running. Errors are
+    /// kept to one line so they fit in the status line.
+    fn reload_config(&self) -> Result<Config, String> {
+        let mut config = self.read_config().map_err(|e| {
+            let message = e.to_string();
+            let mut lines = message.lines().map(str::trim).filter(|l| !l.is_empty());
+            let first = lines.next().unwrap_or_default();
+            match lines.next_back() {
+                Some(last) => format!("{}: {}", first, last),
+                None => first.to_string(),
             }
-        })
-        .unwrap_or_default()
+        })?;
+        config.resolve_theme(self.theme.as_deref(), self)?;
+        fit_to_terminal(&mut config);
+        Ok(config)
     }
 
-    /// Installed languages under config directory
+    /// Installed languages in the language search path
     fn languages(&self) -> io::Result<impl Iterator<Item = OsString>> {
         let builtin = Resources::iter().filter_map(|name| {
             name.strip_prefix("language/")
@@ -242,40 +1135,185 @@ impl Opt {
         });
 
         let configured = self
-            .language_dir()
-            .read_dir()
+            .language_path()
             .into_iter()
-            .flatten()
-            .map_while(Result::ok)
+            .flat_map(|dir| dir.read_dir().into_iter().flatten().map_while(Result::ok))
             .map(|e| e.file_name());
 
         Ok(builtin.chain(configured))
     }
 
-    /// Config directory
-    fn config_dir(&self) -> PathBuf {
-        dirs::config_dir()
-            .expect("Failed to find config directory.")
-            .join("ttyper")
+    /// Contents of a language file, from the language search path or built in.
+    fn language_source(&self, name: &str) -> Option<Vec<u8>> {
+        self.language_path()
+            .iter()
+            .find_map(|dir| fs::read(dir.join(name)).ok())
+            .or_else(|| Resources::get(&format!("language/{}", name)).map(|f| f.data.into_owned()))
     }
 
-    /// Language directory under config directory
-    fn language_dir(&self) -> PathBuf {
-        self.config_dir().join("language")
+    /// Config directory (`TTYPER_CONFIG_DIR`, or ttyper in the platform's config
+    /// directory)
+    fn config_dir(&self) -> Option<PathBuf> {
+        env_dir("TTYPER_CONFIG_DIR").or_else(|| dirs::config_dir().map(|dir| dir.join("ttyper")))
     }
 
-    /// History file path (configurable via config.toml, falls back to default)
-    fn history_file(&self) -> PathBuf {
-        self.config()
-            .history_file
-            .unwrap_or_else(|| self.config_dir().join("history.csv"))
+    /// Data directory for history (`TTYPER_DATA_DIR`, or ttyper in the platform's
+    /// data directory)
+    fn data_dir(&self) -> Option<PathBuf> {
+        env_dir("TTYPER_DATA_DIR").or_else(|| dirs::data_dir().map(|dir| dir.join("ttyper")))
     }
 
-    /// Get the effective language name for history logging
-    fn effective_language(&self) -> String {
-        self.language
-            .clone()
-            .unwrap_or_else(|| self.config().default_language)
+    /// Theme directory under config directory
+    fn theme_dir(&self) -> Option<PathBuf> {
+        self.config_dir().map(|dir| dir.join("themes"))
+    }
+
+    /// Directories searched for languages, in order (`TTYPER_LANGUAGE_PATH`, or the
+    /// language directory under config directory). Built-in languages come last.
+    fn language_path(&self) -> Vec<PathBuf> {
+        match env::var_os("TTYPER_LANGUAGE_PATH").filter(|path| !path.is_empty()) {
+            Some(path) => env::split_paths(&path)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .collect(),
+            None => self
+                .config_dir()
+                .map(|dir| dir.join("language"))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// File with the bookmarks of --book tests, in the data directory.
+    fn bookmarks_file(&self) -> Option<PathBuf> {
+        self.data_dir().map(|dir| dir.join("bookmarks.toml"))
+    }
+
+    /// History file path (configurable via config.toml, falls back to the data
+    /// directory). A history file left in the config directory by older versions is
+    /// used until [`Opt::migrate_history`] has moved it.
+    fn history_file(&self) -> Result<PathBuf, String> {
+        let history_file = self.new_history_file()?;
+        match self.old_history_file(&history_file) {
+            Some(old) if !history_file.exists() => Ok(old),
+            _ => Ok(history_file),
+        }
+    }
+
+    fn new_history_file(&self) -> Result<PathBuf, String> {
+        if let Some(history_file) = &self.config().history_file {
+            return Ok(history_file.clone());
+        }
+        self.data_dir()
+            .map(|dir| dir.join("history.csv"))
+            .ok_or_else(|| {
+                "Error: No data directory found for history. Set TTYPER_DATA_DIR or history_file in config.toml."
+                    .to_string()
+            })
+    }
+
+    /// A history file older versions kept in the config directory, if there is one
+    /// and `history_file` is the default location.
+    fn old_history_file(&self, history_file: &Path) -> Option<PathBuf> {
+        if self.config().history_file.is_some() {
+            return None;
+        }
+        self.config_dir()
+            .map(|dir| dir.join("history.csv"))
+            .filter(|old| old != history_file && old.exists())
+    }
+
+    /// Move a history file left in the config directory by older versions to the data
+    /// directory. When both exist, the old one is left alone with a warning.
+    fn migrate_history(&self) {
+        let Ok(history_file) = self.new_history_file() else {
+            return;
+        };
+        let Some(old) = self.old_history_file(&history_file) else {
+            return;
+        };
+        if history_file.exists() {
+            eprintln!(
+                "Warning: Ignoring old history at {}, as {} exists. Merge it with `ttyper history import` and delete it.",
+                old.display(),
+                history_file.display()
+            );
+        } else if let Err(e) = move_file(&old, &history_file) {
+            eprintln!(
+                "Warning: Cannot move history from '{}' to '{}': {}",
+                old.display(),
+                history_file.display(),
+                e
+            );
+        } else {
+            eprintln!(
+                "Moved history from {} to {}",
+                old.display(),
+                history_file.display()
+            );
+        }
+    }
+}
+
+/// Downgrade truecolor styles when the terminal can't show them.
+fn fit_to_terminal(config: &mut Config) {
+    if !config::supports_truecolor(env::var("COLORTERM").ok().as_deref()) {
+        config.theme.downgrade_truecolor();
+    }
+}
+
+/// A directory set by an environment variable, if it's set and not empty.
+fn env_dir(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Move a file, creating the destination directory. Falls back to copying when the
+/// destination is on another file system.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+impl config::ThemeLoader for Opt {
+    /// Themes in the theme directory take precedence over built-in ones.
+    fn load_theme(&self, name: &str) -> Option<String> {
+        let file = format!("{}.toml", name);
+        self.theme_dir()
+            .and_then(|dir| fs::read_to_string(dir.join(&file)).ok())
+            .or_else(|| {
+                Resources::get(&format!("themes/{}", file))
+                    .and_then(|f| String::from_utf8(f.data.into_owned()).ok())
+            })
+    }
+
+    fn theme_names(&self) -> Vec<String> {
+        let builtin = Resources::iter().filter_map(|name| {
+            name.strip_prefix("themes/")
+                .and_then(|n| n.strip_suffix(".toml"))
+                .map(ToOwned::to_owned)
+        });
+        let configured = self
+            .theme_dir()
+            .into_iter()
+            .flat_map(|dir| dir.read_dir().into_iter().flatten().map_while(Result::ok))
+            .filter_map(|e| {
+                e.file_name()
+                    .to_str()
+                    .and_then(|n| n.strip_suffix(".toml"))
+                    .map(ToOwned::to_owned)
+            });
+
+        let mut names: Vec<String> = builtin.chain(configured).collect();
+        names.sort();
+        names.dedup();
+        names
     }
 }
 
@@ -289,95 +1327,174 @@ impl State {
         &self,
         terminal: &mut Terminal<B>,
         config: &Config,
+        status: Option<&str>,
     ) -> io::Result<()> {
-        match self {
-            State::Test(test) => {
-                terminal.draw(|f| {
-                    f.render_widget(config.apply_to(test), f.size());
-                })?;
+        terminal.draw(|f| {
+            let mut area = f.size();
+            if let Some(status) = status {
+                area.height = area.height.saturating_sub(1);
+                let line = Rect::new(area.x, area.y + area.height, area.width, 1);
+                f.render_widget(config.apply_to(ui::StatusLine(status)), line);
             }
-            State::Results(results) => {
-                terminal.draw(|f| {
-                    f.render_widget(config.apply_to(results), f.size());
-                })?;
+            match self {
+                State::Test(test) => f.render_widget(config.apply_to(test), area),
+                State::Results(results) => f.render_widget(config.apply_to(results), area),
             }
-        }
+        })?;
         Ok(())
     }
 }
 
 fn main() -> io::Result<()> {
-    let opt = Opt::parse();
+    let opt = Opt::parse_args();
     if opt.debug {
         dbg!(&opt);
     }
 
-    let config = opt.config();
     if opt.debug {
-        dbg!(&config);
-    }
-
-    let conflicts = config.key_map.check_conflicts();
-    if !conflicts.is_empty() {
-        for conflict in &conflicts {
-            eprintln!("Warning: {}", conflict);
-        }
-    }
-
-    if opt.list_languages {
-        opt.languages()
-            .unwrap()
-            .for_each(|name| println!("{}", name.to_str().expect("Ill-formatted language name.")));
-
-        return Ok(());
+        dbg!(opt.config());
     }
 
-    let has_history_filters = opt.last.is_some()
-        || opt.history_lang.is_some()
-        || opt.since.is_some()
-        || opt.until.is_some()
-        || opt.stats;
-
-    if has_history_filters && !opt.history {
-        eprintln!(
-            "Error: --last, --history-lang, --since, --until, and --stats require --history flag"
-        );
-        return Ok(());
+    let command = opt.selected_command();
+    if matches!(
+        command,
+        Command::Test(_) | Command::History { .. } | Command::Stats(_)
+    ) {
+        // Before a test starts, so messages can still be seen
+        opt.migrate_history();
     }
 
-    if opt.history {
-        if let Some(ref since) = opt.since {
-            if let Err(msg) = history::validate_date_format(since) {
-                eprintln!("{}", msg);
+    let result = match command {
+        Command::Test(mut args) => match args.apply_config(opt.config()) {
+            Ok(()) => {
+                let mut config = opt.config().clone();
+                fit_to_terminal(&mut config);
+                run_test(&opt, config, &args)?;
+                if let Some(progress) = args.unlock_progress(&opt) {
+                    println!("{}", progress);
+                }
                 return Ok(());
             }
-        }
-        if let Some(ref until) = opt.until {
-            if let Err(msg) = history::validate_date_format(until) {
-                eprintln!("{}", msg);
-                return Ok(());
+            Err(msg) => Err(msg),
+        },
+        Command::History {
+            action: Some(action),
+            ..
+        } => run_history_action(&opt, &action),
+        Command::History { action: None, list } => list.filters.filters().and_then(|filters| {
+            history::show_history(&opt.history_file()?, list.last, &filters);
+            Ok(())
+        }),
+        Command::Stats(args) => args.filters.filters().and_then(|filters| {
+            let view = match args.group_by {
+                Some(group_by) => history::StatsView::Grouped(group_by),
+                None if args.keys => history::StatsView::Keys,
+                None if args.daily => history::StatsView::Daily,
+                None => history::StatsView::Summary,
+            };
+            history::show_stats(&opt.history_file()?, &filters, view);
+            Ok(())
+        }),
+        Command::Languages => {
+            let mut names: Vec<String> = opt
+                .languages()
+                .unwrap()
+                .map(|name| {
+                    name.to_str()
+                        .expect("Ill-formatted language name.")
+                        .to_owned()
+                })
+                .collect();
+            // Installed copies of built-in languages are listed once
+            names.sort();
+            names.dedup();
+            let width = names.iter().map(|name| name.chars().count()).max();
+            for name in &names {
+                let summary = opt
+                    .language_source(name)
+                    .and_then(|bytes| Metadata::read(str::from_utf8(&bytes).ok()?).ok())
+                    .map(|metadata| metadata.summary())
+                    .unwrap_or_default();
+                let line = format!("{:<width$}  {}", name, summary, width = width.unwrap_or(0));
+                println!("{}", line.trim_end());
             }
+            Ok(())
         }
-        if let (Some(ref since), Some(ref until)) = (&opt.since, &opt.until) {
-            if since > until {
-                eprintln!("Error: --since date must be before or equal to --until date");
-                return Ok(());
-            }
+        Command::Config { action: None } => {
+            let none = "(none)".to_string();
+            println!(
+                "Config file:  {}",
+                match opt.config_file() {
+                    Ok(file) if file.exists() => file.display().to_string(),
+                    Ok(file) => format!("{} (not found, using defaults)", file.display()),
+                    Err(_) => format!("{} (set TTYPER_CONFIG_DIR), using defaults", none),
+                }
+            );
+            println!(
+                "Languages:    {}",
+                env::join_paths(opt.language_path())
+                    .ok()
+                    .filter(|path| !path.is_empty())
+                    .map_or(none.clone(), |path| path.to_string_lossy().into_owned())
+            );
+            println!(
+                "Themes:       {}",
+                opt.theme_dir()
+                    .map_or(none.clone(), |dir| dir.display().to_string())
+            );
+            println!(
+                "History file: {}",
+                opt.history_file()
+                    .map_or(none, |file| file.display().to_string())
+            );
+            Ok(())
         }
-        let filters = history::Filters {
-            language: opt.history_lang.as_deref(),
-            since: opt.since.as_deref(),
-            until: opt.until.as_deref(),
-        };
-        if opt.stats {
-            history::show_stats(&opt.history_file(), &filters);
-        } else {
-            history::show_history(&opt.history_file(), opt.last, &filters);
+        Command::Config {
+            action: Some(action),
+        } => run_config_action(&opt, &action),
+    };
+    if let Err(msg) = result {
+        eprintln!("{}", msg);
+    }
+    Ok(())
+}
+
+/// Find a non-global argument given on the command line at a level that also has a
+/// subcommand. Such arguments would otherwise be silently ignored.
+fn misplaced_arg(cmd: &clap::Command, matches: &ArgMatches) -> Option<String> {
+    let (name, sub_matches) = matches.subcommand()?;
+    let misplaced = cmd.get_arguments().find(|arg| {
+        !arg.is_global_set()
+            && matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+    });
+    match misplaced {
+        Some(arg) => Some(arg.to_string()),
+        None => misplaced_arg(cmd.find_subcommand(name)?, sub_matches),
+    }
+}
+
+/// Words of a test, with the breaks after them and, for a chunk of a --book, the word
+/// it starts at.
+struct Contents {
+    words: Vec<String>,
+    breaks: Vec<Break>,
+    book_start: Option<usize>,
+    /// Letters the words were chosen with, for --letters
+    letters: Option<String>,
+}
+
+/// Run typing tests until the user quits.
+fn run_test(opt: &Opt, mut config: Config, args: &TestArgs) -> io::Result<()> {
+    let conflicts = config.key_map.check_conflicts();
+    if !conflicts.is_empty() {
+        for conflict in &conflicts {
+            eprintln!("Warning: {}", conflict);
         }
-        return Ok(());
     }
 
-    let contents = match opt.gen_contents() {
+    // Seed of the words in the current test; practice and repeated tests don't have one
+    let mut seed = Some(args.next_seed());
+    let contents = match args.next_contents(opt, seed.unwrap()) {
         Ok(c) => c,
         Err(msg) => {
             eprintln!("{}", msg);
@@ -385,7 +1502,7 @@ fn main() -> io::Result<()> {
         }
     };
 
-    if contents.is_empty() {
+    if contents.words.is_empty() {
         eprintln!("Error: No words to type. The word list is empty.");
         return Ok(());
     }
@@ -418,174 +1535,219 @@ fn main() -> io::Result<()> {
         io::stdout(),
         PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
     );
+    // Pastes arrive as one event instead of a key press per character
+    let _ = execute!(io::stdout(), EnableBracketedPaste);
     terminal.clear()?;
 
-    let mut state = State::Test(Test::new(
-        contents,
-        !opt.no_backtrack,
-        opt.sudden_death,
-        opt.case_insensitive,
-        opt.no_backspace,
-        opt.look_ahead,
-    ));
+    // Word of the --book the current test starts at, if it's a chunk of one
+    let mut book_start = contents.book_start;
+    let mut letters = contents.letters;
+    // Whether the current test practises words of the one before
+    let mut practice = false;
+    let mut state = State::Test(
+        args.new_test(contents.words, &config)
+            .with_breaks(&contents.breaks),
+    );
+
+    // Shown below the test when the config changed but couldn't be reloaded
+    let mut status: Option<String> = None;
+    let mut watcher = config::Watcher::new(opt.config_file().ok(), opt.theme_dir());
 
-    state.render_into(&mut terminal, &config)?;
+    state.render_into(&mut terminal, &config, status.as_deref())?;
+    // Keys of a multi-key sequence pressed so far
+    let mut pending = Vec::new();
+    // Events read while looking for a burst of typed text, to handle next
+    let mut queued: VecDeque<Event> = VecDeque::new();
     loop {
-        let event = event::read()?;
+        let (event, from_queue) = match queued.pop_front() {
+            Some(event) => (event, true),
+            None => {
+                // Apply changes to the theme and key map while waiting for input
+                if !event::poll(CONFIG_CHECK_INTERVAL)? {
+                    if watcher.changed() {
+                        match opt.reload_config() {
+                            Ok(reloaded) => {
+                                config.theme = reloaded.theme;
+                                config.key_map = reloaded.key_map;
+                                if let State::Test(ref mut test) = state {
+                                    test.keys = config.key_map.edit_keys();
+                                }
+                                pending.clear();
+                                status = None;
+                            }
+                            Err(e) => status = Some(format!("Config not reloaded: {}", e)),
+                        }
+                        state.render_into(&mut terminal, &config, status.as_deref())?;
+                    }
+                    continue;
+                }
+                (event::read()?, false)
+            }
+        };
+
+        let lookup = match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                let screen = match state {
+                    State::Test(_) => Screen::Test,
+                    State::Results(_) => Screen::Results,
+                };
+                config
+                    .key_map
+                    .press(screen, &mut pending, key.code, key.modifiers)
+            }
+            _ => Lookup::Unbound,
+        };
 
         // handle exit controls
-        match event {
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                kind: KeyEventKind::Press,
-                modifiers: KeyModifiers::CONTROL,
-                ..
-            }) => break,
-            Event::Key(KeyEvent {
-                code: KeyCode::Esc,
-                kind: KeyEventKind::Press,
-                modifiers: KeyModifiers::NONE,
-                ..
-            }) => match state {
-                State::Test(ref test) => {
-                    let results = Results::from(test);
-                    if !opt.no_save {
-                        history::save_results(
-                            &opt.history_file(),
-                            &opt.effective_language(),
-                            opt.words.get(),
-                            &results,
-                        );
+        match lookup {
+            Lookup::Action(Action::Abort) => break,
+            Lookup::Action(Action::Finish) => {
+                match state {
+                    State::Test(ref test) => {
+                        state = State::Results(args.results(
+                            opt,
+                            test,
+                            seed,
+                            book_start,
+                            letters.as_deref(),
+                            practice,
+                        ))
                     }
-                    state = State::Results(results);
+                    State::Results(_) => break,
                 }
-                State::Results(_) => break,
-            },
+                state.render_into(&mut terminal, &config, status.as_deref())?;
+                continue;
+            }
+            Lookup::Pending => continue,
             _ => {}
         }
 
         match state {
-            State::Test(ref mut test) => {
-                if let Event::Key(key) = event {
+            State::Test(ref mut test) => match lookup {
+                Lookup::Action(Action::Pause) => test.toggle_pause(),
+                // Keys other than pause, finish and abort wait for the test to resume
+                _ if test.is_paused() => {}
+                Lookup::Action(Action::NewTest) => {
                     // new_test binding (default: TAB) → restart with new words (no save)
-                    if key.kind == KeyEventKind::Press
-                        && config.key_map.new_test.matches(key.code, key.modifiers)
-                    {
-                        match opt.gen_contents() {
-                            Ok(contents) if !contents.is_empty() => {
-                                state = State::Test(Test::new(
-                                    contents,
-                                    !opt.no_backtrack,
-                                    opt.sudden_death,
-                                    opt.case_insensitive,
-                                    opt.no_backspace,
-                                    opt.look_ahead,
-                                ));
-                            }
-                            _ => continue,
-                        }
-                    } else {
-                        test.handle_key(key);
-                        if test.complete {
-                            let results = Results::from(&*test);
-                            if !opt.no_save {
-                                history::save_results(
-                                    &opt.history_file(),
-                                    &opt.effective_language(),
-                                    opt.words.get(),
-                                    &results,
-                                );
-                            }
-                            state = State::Results(results);
+                    let next = args.next_seed();
+                    match args.next_contents(opt, next) {
+                        Ok(contents) if !contents.words.is_empty() => {
+                            seed = Some(next);
+                            book_start = contents.book_start;
+                            letters = contents.letters;
+                            practice = false;
+                            state = State::Test(
+                                args.new_test(contents.words, &config)
+                                    .with_breaks(&contents.breaks),
+                            );
                         }
+                        _ => continue,
                     }
                 }
-            }
-            State::Results(ref result) => {
-                if let Event::Key(key) = event {
-                    if key.kind != KeyEventKind::Press {
-                        continue;
-                    }
-                    if config.key_map.restart.matches(key.code, key.modifiers) {
-                        match opt.gen_contents() {
-                            Ok(contents) if !contents.is_empty() => {
-                                state = State::Test(Test::new(
-                                    contents,
-                                    !opt.no_backtrack,
-                                    opt.sudden_death,
-                                    opt.case_insensitive,
-                                    opt.no_backspace,
-                                    opt.look_ahead,
-                                ));
+                Lookup::Action(Action::RestartTest) => {
+                    let words = test.words.iter().map(|w| w.text.clone()).collect();
+                    let breaks = test.breaks();
+                    state = State::Test(args.new_test(words, &config).with_breaks(&breaks));
+                }
+                _ => {
+                    match event {
+                        // Events already queued were checked for bursts when they were read
+                        Event::Key(key) if from_queue => test.handle_key(key),
+                        Event::Key(key) => {
+                            let (burst, rest) = read_burst(key)?;
+                            if burst.is_empty() {
+                                test.handle_key(key);
+                            } else {
+                                test.handle_burst(&burst);
                             }
-                            _ => continue,
-                        }
-                    } else if config
-                        .key_map
-                        .practice_missed
-                        .matches(key.code, key.modifiers)
-                    {
-                        if result.missed_words.is_empty() {
-                            continue;
+                            queued.extend(rest);
                         }
-                        let mut practice_words: Vec<String> = (result.missed_words)
-                            .iter()
-                            .flat_map(|w| vec![w.clone(); 5])
-                            .collect();
-                        practice_words.shuffle(&mut thread_rng());
-                        state = State::Test(Test::new(
-                            practice_words,
-                            !opt.no_backtrack,
-                            opt.sudden_death,
-                            opt.case_insensitive,
-                            opt.no_backspace,
-                            opt.look_ahead,
-                        ));
-                    } else if config.key_map.repeat.matches(key.code, key.modifiers) {
-                        if result.words.is_empty() {
-                            continue;
-                        }
-                        state = State::Test(Test::new(
-                            result.words.clone(),
-                            !opt.no_backtrack,
-                            opt.sudden_death,
-                            opt.case_insensitive,
-                            opt.no_backspace,
-                            opt.look_ahead,
+                        Event::Paste(text) => test.handle_text(&text),
+                        _ => {}
+                    }
+                    if test.complete {
+                        state = State::Results(args.results(
+                            opt,
+                            test,
+                            seed,
+                            book_start,
+                            letters.as_deref(),
+                            practice,
                         ));
-                    } else if config
-                        .key_map
-                        .practice_slow
-                        .matches(key.code, key.modifiers)
-                    {
-                        if result.slow_words.is_empty() {
-                            continue;
+                    }
+                }
+            },
+            State::Results(ref result) => match lookup {
+                Lookup::Action(Action::Restart) => {
+                    let next = args.next_seed();
+                    match args.next_contents(opt, next) {
+                        Ok(contents) if !contents.words.is_empty() => {
+                            seed = Some(next);
+                            book_start = contents.book_start;
+                            letters = contents.letters;
+                            practice = false;
+                            state = State::Test(
+                                args.new_test(contents.words, &config)
+                                    .with_breaks(&contents.breaks),
+                            );
                         }
-                        let mut practice_words: Vec<String> = result
-                            .slow_words
-                            .iter()
-                            .flat_map(|w| vec![w.clone(); 5])
-                            .collect();
-                        practice_words.shuffle(&mut thread_rng());
-                        state = State::Test(Test::new(
-                            practice_words,
-                            !opt.no_backtrack,
-                            opt.sudden_death,
-                            opt.case_insensitive,
-                            opt.no_backspace,
-                            opt.look_ahead,
-                        ));
-                    } else if config.key_map.quit.matches(key.code, key.modifiers) {
-                        break;
+                        _ => continue,
                     }
                 }
-            }
+                Lookup::Action(Action::PracticeMissed) => {
+                    if result.missed_words.is_empty() {
+                        continue;
+                    }
+                    let mut practice_words: Vec<String> = (result.missed_words)
+                        .iter()
+                        .flat_map(|w| vec![w.clone(); 5])
+                        .collect();
+                    practice_words.shuffle(&mut thread_rng());
+                    seed = None;
+                    book_start = None;
+                    letters = None;
+                    practice = true;
+                    state = State::Test(args.new_test(practice_words, &config));
+                }
+                Lookup::Action(Action::Repeat) => {
+                    if result.words.is_empty() {
+                        continue;
+                    }
+                    seed = None;
+                    book_start = None;
+                    letters = None;
+                    practice = true;
+                    state = State::Test(args.new_test(result.words.clone(), &config));
+                }
+                Lookup::Action(Action::PracticeSlow) => {
+                    if result.slow_words.is_empty() {
+                        continue;
+                    }
+                    let mut practice_words: Vec<String> = result
+                        .slow_words
+                        .iter()
+                        .flat_map(|w| vec![w.clone(); 5])
+                        .collect();
+                    practice_words.shuffle(&mut thread_rng());
+                    seed = None;
+                    book_start = None;
+                    letters = None;
+                    practice = true;
+                    state = State::Test(args.new_test(practice_words, &config));
+                }
+                Lookup::Action(Action::Quit) => break,
+                _ => continue,
+            },
         }
 
-        state.render_into(&mut terminal, &config)?;
+        state.render_into(&mut terminal, &config, status.as_deref())?;
     }
 
-    let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+    let _ = execute!(
+        io::stdout(),
+        DisableBracketedPaste,
+        PopKeyboardEnhancementFlags
+    );
     terminal::disable_raw_mode()?;
     execute!(
         io::stdout(),
@@ -597,6 +1759,238 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
+/// Key presses that were already waiting to be read after `key`, if they type
+/// non-ASCII text with it: text an input method committed at once, or fast typing in a
+/// script like Cyrillic that piled up while the screen was drawn. ASCII keys stay
+/// separate key presses. Returns the presses, starting with `key`, and the events read
+/// that aren't part of them, to handle next.
+fn read_burst(key: KeyEvent) -> io::Result<(Vec<KeyEvent>, Vec<Event>)> {
+    let typed = |key: &KeyEvent| match key.code {
+        KeyCode::Char(c)
+            if key.kind == KeyEventKind::Press
+                && !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            Some(c)
+        }
+        _ => None,
+    };
+    if typed(&key).is_none() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let mut keys = vec![key];
+    let mut read = Vec::new();
+    while event::poll(Duration::ZERO)? {
+        let event = event::read()?;
+        match &event {
+            Event::Key(key) if typed(key).is_some() => keys.push(*key),
+            // Anything else ends the burst, like a release between presses, which
+            // means they were typed one by one
+            _ => {
+                read.push(event);
+                break;
+            }
+        }
+    }
+    if keys.len() > 1
+        && keys
+            .iter()
+            .any(|key| typed(key).is_some_and(|c| !c.is_ascii()))
+    {
+        return Ok((keys, read));
+    }
+    // Handled one by one, like any other key presses
+    read.splice(0..0, keys[1..].iter().map(|&key| Event::Key(key)));
+    Ok((Vec::new(), read))
+}
+
+/// Run a `ttyper config` subcommand.
+fn run_config_action(opt: &Opt, action: &ConfigAction) -> Result<(), String> {
+    let config_file = opt.config_file()?;
+    match action {
+        ConfigAction::Init { force } => {
+            if config_file.exists() && !force {
+                return Err(format!(
+                    "Error: '{}' already exists. Use --force to overwrite it.",
+                    config_file.display()
+                ));
+            }
+            if let Some(dir) = config_file.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Error: Cannot create '{}': {}", dir.display(), e))?;
+            }
+            fs::write(&config_file, config::DEFAULT_CONFIG)
+                .map_err(|e| format!("Error: Cannot write '{}': {}", config_file.display(), e))?;
+            println!("Wrote default configuration to {}", config_file.display());
+        }
+        ConfigAction::Check => {
+            let source = match fs::read_to_string(&config_file) {
+                Ok(source) => source,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    println!(
+                        "No config file at {}; using defaults. Run `ttyper config init` to create one.",
+                        config_file.display()
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "Error: Cannot read '{}': {}",
+                        config_file.display(),
+                        e
+                    ))
+                }
+            };
+            let report = Config::check(&source, opt);
+            for error in &report.errors {
+                eprintln!("Error: {}", error.trim_end());
+            }
+            for warning in &report.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            let summary = format!(
+                "{}: {} errors, {} warnings.",
+                config_file.display(),
+                report.errors.len(),
+                report.warnings.len()
+            );
+            if !report.errors.is_empty() {
+                // Unlike other errors, a failed check sets the exit status for scripts
+                eprintln!("{}", summary);
+                std::process::exit(1);
+            } else if report.warnings.is_empty() {
+                println!("{} is valid.", config_file.display());
+            } else {
+                println!("{}", summary);
+            }
+        }
+        ConfigAction::Show => {
+            let config = opt.config();
+            println!("# Effective configuration from {}", config_file.display());
+            println!("# history_file shows the resolved path, even when not set.");
+            let mut effective = config.to_toml();
+            if config.history_file.is_none() {
+                // Keep it a top-level key, ahead of the first table
+                let path = toml::Value::String(opt.history_file()?.display().to_string());
+                effective = format!("history_file = {}\n{}", path, effective);
+            }
+            print!("{}", effective);
+        }
+    }
+    Ok(())
+}
+
+/// Run a `ttyper history` subcommand.
+fn run_history_action(opt: &Opt, action: &HistoryAction) -> Result<(), String> {
+    use history::edit::{self, Selection};
+    use history::interop;
+
+    match action {
+        HistoryAction::Export { format, output } => {
+            let records = interop::read_records(&opt.history_file()?)?;
+            let exported = interop::export(&records, *format)?;
+            match output {
+                Some(path) => fs::write(path, exported)
+                    .map_err(|e| format!("Error: Cannot write '{}': {}", path.display(), e))?,
+                None => print!("{}", exported),
+            }
+        }
+        HistoryAction::Import { file } => {
+            let (added, skipped) = interop::import(&opt.history_file()?, file)?;
+            println!(
+                "Imported {} new entries ({} duplicates skipped).",
+                added, skipped
+            );
+        }
+        HistoryAction::Verify => {
+            let history_file = opt.history_file()?;
+            let problems = history::verify::verify_file(&history_file)?;
+            for problem in &problems {
+                println!("line {}: {}", problem.line, problem.message);
+            }
+            if problems.is_empty() {
+                println!("No problems found in {}.", history_file.display());
+            } else {
+                println!(
+                    "{} problems found in {}. Run `ttyper history repair` to fix them.",
+                    problems.len(),
+                    history_file.display()
+                );
+            }
+        }
+        HistoryAction::Repair => match history::verify::repair_file(&opt.history_file()?)? {
+            Some((repair, backup)) => println!(
+                "Repaired history: kept {} entries, recovered {}, removed {} malformed lines. \
+                 Backup saved to {}.",
+                repair.kept,
+                repair.recovered,
+                repair.removed,
+                backup.display()
+            ),
+            None => println!("Nothing to repair."),
+        },
+        HistoryAction::Delete {
+            ids,
+            filters,
+            dry_run,
+        } => {
+            let filters = filters.filters()?;
+            let selection = if !ids.is_empty() {
+                Selection::Entries(ids)
+            } else if filters.is_active() {
+                Selection::Filter(&filters)
+            } else {
+                return Err(
+                    "Error: Specify entry IDs or at least one of --lang, --since, --until, --tag"
+                        .to_string(),
+                );
+            };
+            let removed = edit::delete(&opt.history_file()?, &selection, *dry_run)?;
+            report_deleted(&removed, *dry_run);
+        }
+        HistoryAction::Tag { ids, add, remove } => {
+            if add.is_empty() && remove.is_empty() {
+                return Err("Error: Specify tags with --add or --remove".to_string());
+            }
+            edit::tag(&opt.history_file()?, ids, add, remove)?;
+            println!("Updated tags on {} entries.", ids.len());
+        }
+        HistoryAction::Annotate { id, note } => {
+            edit::annotate(&opt.history_file()?, id, note)?;
+            if note.is_empty() {
+                println!("Removed note from entry {}.", id);
+            } else {
+                println!("Added note to entry {}.", id);
+            }
+        }
+        HistoryAction::Prune { before, dry_run } => {
+            let removed = edit::delete(&opt.history_file()?, &Selection::Before(before), *dry_run)?;
+            report_deleted(&removed, *dry_run);
+        }
+    }
+    Ok(())
+}
+
+/// Print the entries removed (or that would be removed) by a history edit.
+fn report_deleted(removed: &[(usize, history::record::Record)], dry_run: bool) {
+    for (id, record) in removed {
+        println!(
+            "{:>4} {:<20} {:<15} {:>8.1} WPM",
+            id, record.datetime, record.language, record.wpm_adjusted
+        );
+    }
+    if dry_run {
+        println!("Would delete {} entries.", removed.len());
+    } else {
+        println!("Deleted {} entries.", removed.len());
+        if !removed.is_empty() {
+            println!("Later entries have new IDs. Run `ttyper history` to see them.");
+        }
+    }
+}
+
 /// Reattach stdin to /dev/tty so crossterm can read keyboard events
 /// after stdin was consumed by a pipe.
 #[cfg(unix)]
@@ -609,3 +2003,301 @@ fn reattach_stdin() -> io::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_args(args: &[&str]) -> TestArgs {
+        let opt = Opt::try_parse_from([&["ttyper"], args].concat()).unwrap();
+        match opt.selected_command() {
+            Command::Test(args) => *args,
+            command => panic!("expected a test, got {:?}", command),
+        }
+    }
+
+    fn config_with_profile() -> Config {
+        toml::from_str(
+            r#"
+            [profile.drill]
+            words = 25
+            language = "german"
+            sudden_death = true
+            look_ahead = 3
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn bundled_frequency_lists_are_weighted_by_rank() {
+        let weights = |name: &str| {
+            let data = Resources::get(&format!("language/{}", name)).unwrap().data;
+            Metadata::read(str::from_utf8(&data).unwrap())
+                .unwrap()
+                .weights
+        };
+        for name in [
+            "english1000",
+            "french1000",
+            "german10000",
+            "russian1000",
+            "thai",
+        ] {
+            assert_eq!(weights(name), language::Weights::Rank, "{}", name);
+        }
+        // Sorted alphabetically, not by frequency
+        assert_eq!(weights("russian10000"), language::Weights::None);
+    }
+
+    #[test]
+    fn profile_overrides_defaults() {
+        let mut args = test_args(&["--profile", "drill"]);
+        args.apply_config(&config_with_profile()).unwrap();
+        assert_eq!(args.words(), 25);
+        assert_eq!(args.language.as_deref(), Some("german"));
+        assert!(args.sudden_death);
+        assert_eq!(args.look_ahead, Some(3));
+        assert!(!args.no_backtrack);
+    }
+
+    #[test]
+    fn command_line_overrides_profile() {
+        let mut args = test_args(&[
+            "--profile",
+            "drill",
+            "-w",
+            "10",
+            "--language-file",
+            "words.txt",
+            "--look-ahead",
+            "1",
+        ]);
+        args.apply_config(&config_with_profile()).unwrap();
+        assert_eq!(args.words(), 10);
+        assert_eq!(args.language, None);
+        assert_eq!(args.language_file, Some(PathBuf::from("words.txt")));
+        assert_eq!(args.look_ahead, Some(1));
+    }
+
+    #[test]
+    fn defaults_without_profile() {
+        let mut args = test_args(&["test"]);
+        args.apply_config(&config_with_profile()).unwrap();
+        assert_eq!(args.words(), DEFAULT_WORDS);
+        assert!(!args.sudden_death);
+    }
+
+    #[test]
+    fn config_defaults_apply_below_profile_and_flags() {
+        let config: Config = toml::from_str(
+            r#"
+            [defaults]
+            words = 30
+            language = "french"
+            no_backtrack = true
+            no_shuffle = true
+            look_ahead = 2
+
+            [profile.drill]
+            words = 25
+            language = "german"
+            sudden_death = true
+            "#,
+        )
+        .unwrap();
+
+        let mut args = test_args(&["test"]);
+        args.apply_config(&config).unwrap();
+        assert_eq!(args.words(), 30);
+        assert_eq!(args.language.as_deref(), Some("french"));
+        assert!(args.no_backtrack && args.no_shuffle && !args.sudden_death);
+        assert_eq!(args.look_ahead, Some(2));
+
+        let mut args = test_args(&[
+            "--profile",
+            "drill",
+            "--backtrack",
+            "--shuffle",
+            "--no-look-ahead",
+        ]);
+        args.apply_config(&config).unwrap();
+        assert_eq!(args.words(), 25);
+        assert_eq!(args.language.as_deref(), Some("german"));
+        assert!(!args.no_backtrack && !args.no_shuffle && args.sudden_death);
+        assert_eq!(args.look_ahead, None);
+
+        // The last of a flag and its negation wins
+        let mut args = test_args(&["--backtrack", "--no-backtrack", "--no-sudden-death"]);
+        args.apply_config(&config).unwrap();
+        assert!(args.no_backtrack && !args.sudden_death);
+    }
+
+    #[test]
+    fn unknown_profile_is_an_error() {
+        let mut args = test_args(&["--profile", "nope"]);
+        assert!(args.apply_config(&config_with_profile()).is_err());
+    }
+
+    #[test]
+    fn reload_keeps_errors_to_one_line() {
+        let dir = env::temp_dir().join("ttyper_test_reload");
+        fs::create_dir_all(&dir).unwrap();
+        let config_file = dir.join("config.toml");
+        let opt = Opt::try_parse_from(["ttyper", "-c", config_file.to_str().unwrap()]).unwrap();
+
+        fs::write(&config_file, "[theme]\ntitle = \"notacolor\"\n").unwrap();
+        let error = opt.reload_config().unwrap_err();
+        assert!(!error.contains('\n'), "{}", error);
+        assert!(error.contains("line 2"), "{}", error);
+
+        fs::write(&config_file, "[key_map]\nquit = \"x\"\n").unwrap();
+        let config = opt.reload_config().unwrap();
+        assert!(config.key_map.quit.matches(
+            crossterm::event::KeyCode::Char('x'),
+            crossterm::event::KeyModifiers::NONE
+        ));
+
+        fs::write(&config_file, "theme = \"nope\"\n").unwrap();
+        assert!(opt.reload_config().unwrap_err().contains("nope"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn pseudo_words_repeat_with_seed() {
+        let dir = env::temp_dir().join("ttyper_test_pseudo");
+        fs::create_dir_all(&dir).unwrap();
+        let corpus = dir.join("corpus.txt");
+        fs::write(
+            &corpus,
+            "the then there these\nother mother brother weather\n",
+        )
+        .unwrap();
+        let corpus = corpus.to_str().unwrap();
+        let opt = Opt::try_parse_from(["ttyper", "-c", "/nonexistent/config.toml"]).unwrap();
+
+        let args = test_args(&[corpus, "--pseudo", "-w", "12", "--bias", "o,"]);
+        let words = args.gen_contents(&opt, 9, None).unwrap();
+        assert_eq!(words.len(), 12);
+        assert_eq!(args.gen_contents(&opt, 9, None).unwrap(), words);
+        assert_eq!(
+            args.options(Some(9), None, false),
+            "pseudo=3;bias=o%2C;seed=9"
+        );
+        assert!(args
+            .gen_contents(&opt, 9, Some("the"))
+            .unwrap()
+            .iter()
+            .all(|word| word.chars().all(|c| "the".contains(c))));
+        assert_eq!(
+            args.options(Some(9), Some("the"), false),
+            "pseudo=3;bias=o%2C;letters=the;seed=9"
+        );
+
+        assert!(Opt::try_parse_from(["ttyper", "--pseudo", "--order", "0"]).is_err());
+        assert!(Opt::try_parse_from(["ttyper", "--bias", "abc"]).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn practice_rounds_are_saved_without_word_options() {
+        let args = test_args(&["-l", "english1000", "--top", "500", "--letters", "arst"]);
+        assert_eq!(
+            args.options(Some(4), Some("arst"), false),
+            "top=500;letters=arst;seed=4"
+        );
+        assert_eq!(args.options(Some(4), Some("arst"), true), "practice");
+
+        // A fast practice round of the words before doesn't unlock letters
+        let practice = history::record::Record::parse(&format!(
+            "2026-02-10 10:00:00,english1000,50,90,90,100,200,200,,,,,,,{}",
+            args.options(None, None, true)
+        ))
+        .unwrap();
+        assert_eq!(
+            letters::unlocked("arst", "n", &[practice], args.thresholds()),
+            "arst"
+        );
+    }
+
+    #[test]
+    fn seeded_words_are_stable() {
+        let opt = Opt::try_parse_from(["ttyper", "-c", "/nonexistent/config.toml"]).unwrap();
+        let words = test_args(&["-l", "english200", "-w", "8"])
+            .gen_contents(&opt, 83712, None)
+            .unwrap();
+        // Test codes only work if this never changes, so a new RNG or sampling
+        // algorithm must not slip in with a dependency update
+        assert_eq!(
+            words,
+            ["the", "be", "an", "the", "for", "had", "by", "page"]
+        );
+    }
+
+    #[test]
+    fn test_codes_reproduce_tests() {
+        let opt = Opt::try_parse_from(["ttyper", "-c", "/nonexistent/config.toml"]).unwrap();
+        let config = Config::default();
+        let mut args = test_args(&[
+            "-l",
+            "english200",
+            "-w",
+            "20",
+            "--sudden-death",
+            "--top",
+            "100",
+            "--min-len",
+            "3",
+        ]);
+        args.apply_config(&config).unwrap();
+        let words = args.gen_contents(&opt, 77, None).unwrap();
+        let code = args.test_code(&opt, 77, None).unwrap().to_string();
+        assert_eq!(code, "english200:20:77:d:top=100;min_len=3");
+
+        let mut taken = test_args(&["--code", &code]);
+        taken.apply_config(&config).unwrap();
+        assert!(taken.sudden_death && !taken.no_backtrack);
+        assert_eq!(
+            taken.gen_contents(&opt, taken.next_seed(), None).unwrap(),
+            words
+        );
+        assert_eq!(taken.test_code(&opt, 77, None).unwrap().to_string(), code);
+
+        assert!(Opt::try_parse_from(["ttyper", "--code", &code, "-w", "5"]).is_err());
+        let mut unknown = test_args(&["--code", "english200:20:77::future=1"]);
+        assert!(unknown.apply_config(&config).is_err());
+        assert!(test_args(&["words.txt"]).test_code(&opt, 1, None).is_none());
+    }
+
+    #[test]
+    fn daily_challenge_ignores_config_options() {
+        let config: Config = toml::from_str(
+            r#"
+            [defaults]
+            words = 10
+            language = "german"
+            sudden_death = true
+            "#,
+        )
+        .unwrap();
+        let mut args = test_args(&["--daily"]);
+        args.apply_config(&config).unwrap();
+        let date = chrono::Local::now().date_naive();
+        assert_eq!(args.language.as_deref(), Some(DAILY_LANGUAGE));
+        assert_eq!(args.words(), DAILY_WORDS);
+        assert!(!args.sudden_death);
+        assert_eq!(args.seed, Some(daily_code(date).seed));
+        assert_eq!(
+            args.options(args.seed, None, false),
+            format!("seed={};daily={}", date.format("%Y%m%d"), date)
+        );
+
+        assert_eq!(
+            daily_code("2026-10-18".parse().unwrap()).to_string(),
+            "english1000:50:20261018"
+        );
+        assert!(Opt::try_parse_from(["ttyper", "--daily", "-l", "german"]).is_err());
+    }
+}
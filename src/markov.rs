@@ -0,0 +1,210 @@
+//! Pseudo-words from a character n-gram Markov model, so tests practise real letter
+//! patterns without words that can be typed from memory.
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Marks the start of a word in a context.
+const START: char = '\u{2}';
+/// How many times more likely a biased letter is to be picked.
+const BIAS_FACTOR: f64 = 5.0;
+/// How many times to try for a word that isn't in the training words.
+const ATTEMPTS: usize = 20;
+
+/// Which letters follow each context of `order - 1` letters, and how often.
+#[derive(Debug)]
+pub struct Model {
+    order: usize,
+    /// Next letters for each context; None ends the word.
+    transitions: HashMap<Vec<char>, Vec<(Option<char>, f64)>>,
+    known: HashSet<String>,
+    max_len: usize,
+}
+
+impl Model {
+    /// Build a model of the given order (letters per n-gram, at least 1) from words
+    /// and their weights.
+    pub fn train<'a>(words: impl IntoIterator<Item = (&'a str, f64)>, order: usize) -> Model {
+        let order = order.max(1);
+        let mut counts: HashMap<Vec<char>, HashMap<Option<char>, f64>> = HashMap::new();
+        let mut known = HashSet::new();
+        let mut max_len = 0;
+
+        for (word, weight) in words {
+            if word.is_empty() || weight <= 0.0 || !known.insert(word.to_owned()) {
+                continue;
+            }
+            let letters: Vec<char> = word.chars().collect();
+            max_len = max_len.max(letters.len());
+
+            let mut context = vec![START; order - 1];
+            for next in letters.iter().copied().map(Some).chain([None]) {
+                *counts
+                    .entry(context.clone())
+                    .or_default()
+                    .entry(next)
+                    .or_default() += weight;
+                if let Some(letter) = next {
+                    if !context.is_empty() {
+                        context.remove(0);
+                        context.push(letter);
+                    }
+                }
+            }
+        }
+
+        let transitions = counts
+            .into_iter()
+            .map(|(context, next)| {
+                let mut next: Vec<(Option<char>, f64)> = next.into_iter().collect();
+                // Fixed order, so a seeded generator always gives the same words
+                next.sort_by_key(|&(letter, _)| letter);
+                (context, next)
+            })
+            .collect();
+
+        Model {
+            order,
+            transitions,
+            known,
+            max_len,
+        }
+    }
+
+    /// Make the given letters more likely wherever they can follow.
+    pub fn bias(&mut self, letters: &str) {
+        for next in self.transitions.values_mut() {
+            for (letter, weight) in next {
+                if letter.is_some_and(|letter| letters.contains(letter)) {
+                    *weight *= BIAS_FACTOR;
+                }
+            }
+        }
+    }
+
+    /// Use only letters for which `allowed` is true. Words end early where no
+    /// allowed letter can follow.
+    pub fn restrict(&mut self, allowed: impl Fn(char) -> bool) {
+        for next in self.transitions.values_mut() {
+            next.retain(|&(letter, _)| letter.map_or(true, &allowed));
+            if next.is_empty() {
+                next.push((None, 1.0));
+            }
+        }
+    }
+
+    /// Generate one word, preferring ones that aren't in the training words. None if
+    /// the model has no words.
+    pub fn generate(&self, rng: &mut impl Rng) -> Option<String> {
+        let mut word = None;
+        for _ in 0..ATTEMPTS {
+            word = self.walk(rng);
+            match &word {
+                Some(w) if w.is_empty() || self.known.contains(w) => continue,
+                _ => break,
+            }
+        }
+        word.filter(|w| !w.is_empty())
+    }
+
+    /// Generate `count` words.
+    pub fn words(&self, count: usize, rng: &mut impl Rng) -> Vec<String> {
+        (0..count).map_while(|_| self.generate(rng)).collect()
+    }
+
+    /// Follow the model from the start of a word to its end, giving up on words
+    /// longer than any training word.
+    fn walk(&self, rng: &mut impl Rng) -> Option<String> {
+        let mut context = vec![START; self.order - 1];
+        let mut word = String::new();
+        for _ in 0..=self.max_len {
+            let next = self.transitions.get(&context)?;
+            let index = WeightedIndex::new(next.iter().map(|&(_, weight)| weight)).ok()?;
+            match next[index.sample(rng)].0 {
+                Some(letter) => {
+                    word.push(letter);
+                    if !context.is_empty() {
+                        context.remove(0);
+                        context.push(letter);
+                    }
+                }
+                None => return Some(word),
+            }
+        }
+        // Too long; cut it at the longest training word instead of looping again
+        Some(word.chars().take(self.max_len).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const CORPUS: [&str; 8] = [
+        "the", "then", "there", "these", "other", "mother", "brother", "weather",
+    ];
+
+    fn model(order: usize) -> Model {
+        Model::train(CORPUS.iter().map(|&w| (w, 1.0)), order)
+    }
+
+    #[test]
+    fn generates_words_from_seen_letter_patterns() {
+        let model = model(3);
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let words = model.words(50, &mut rng);
+        assert_eq!(words.len(), 50);
+        for word in &words {
+            assert!(!word.is_empty() && word.chars().count() <= 7, "{}", word);
+            // Every word starts with two letters that start a training word
+            let start: String = word.chars().take(2).collect();
+            assert!(CORPUS.iter().any(|w| w.starts_with(&start)), "{}", word);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_words() {
+        let model = model(2);
+        let first = model.words(20, &mut ChaCha8Rng::seed_from_u64(42));
+        let second = model.words(20, &mut ChaCha8Rng::seed_from_u64(42));
+        assert_eq!(first, second);
+        assert_eq!(first[..3], ["ther", "mothero", "ther"]);
+    }
+
+    #[test]
+    fn bias_favors_chosen_letters() {
+        let count_b = |model: &Model| {
+            model
+                .words(200, &mut ChaCha8Rng::seed_from_u64(1))
+                .concat()
+                .matches('b')
+                .count()
+        };
+        let plain = model(1);
+        let mut biased = model(1);
+        biased.bias("b");
+        assert!(count_b(&biased) > 2 * count_b(&plain));
+    }
+
+    #[test]
+    fn restricted_words_use_only_allowed_letters() {
+        let mut model = model(3);
+        model.restrict(|c| "theor".contains(c));
+        let words = model.words(30, &mut ChaCha8Rng::seed_from_u64(5));
+        assert_eq!(words.len(), 30);
+        for word in words {
+            assert!(word.chars().all(|c| "theor".contains(c)), "{}", word);
+        }
+    }
+
+    #[test]
+    fn empty_model_generates_nothing() {
+        let model = Model::train(std::iter::empty(), 3);
+        assert!(model.words(5, &mut ChaCha8Rng::seed_from_u64(0)).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+//! Dead keys: accents typed before the letter they go on, which some terminals pass
+//! on as a separate key instead of composing the letter themselves.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// The combining mark a dead key adds to the next letter, for the spacing accents dead
+/// keys type on their own and for combining marks.
+pub fn dead_key_mark(c: char) -> Option<char> {
+    let mark = match c {
+        '`' => '\u{0300}',
+        '´' | '\'' => '\u{0301}',
+        '^' => '\u{0302}',
+        '~' => '\u{0303}',
+        '¯' => '\u{0304}',
+        '˘' => '\u{0306}',
+        '˙' => '\u{0307}',
+        '¨' | '"' => '\u{0308}',
+        '˚' => '\u{030A}',
+        '˝' => '\u{030B}',
+        'ˇ' => '\u{030C}',
+        '¸' => '\u{0327}',
+        '˛' => '\u{0328}',
+        '\u{0300}'..='\u{036F}' => c,
+        _ => return None,
+    };
+    Some(mark)
+}
+
+/// Whether the dead key `c` starts the grapheme `next`, rather than being typed as
+/// itself: `next` has its mark but doesn't start with `c`. ASCII quotes and accents
+/// are only dead keys with `ascii`, as they're usually typed as themselves.
+pub fn starts(c: char, next: &str, ascii: bool) -> bool {
+    (ascii || !c.is_ascii())
+        && dead_key_mark(c)
+            .is_some_and(|mark| !next.starts_with(c) && next.nfd().any(|m| m == mark))
+}
+
+/// The text of the dead key `dead` followed by `c`: the letter with the accent if there
+/// is one, or both characters as typed.
+pub fn compose(dead: char, c: char) -> String {
+    let Some(mark) = dead_key_mark(dead) else {
+        return format!("{}{}", dead, c);
+    };
+    // The terminal composed the letter and passed on the dead key as well
+    if c.nfd().any(|m| m == mark) {
+        return c.to_string();
+    }
+    let composed: String = [c, mark].into_iter().nfc().collect();
+    if composed.chars().count() == 1 {
+        composed
+    } else {
+        format!("{}{}", dead, c)
+    }
+}
+
+/// `text` with each non-ASCII dead key composed with the letter after it, for text that
+/// arrives at once. ASCII quotes and accents are left alone, as they're usually typed as
+/// themselves.
+pub fn compose_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if !c.is_ascii() && dead_key_mark(c).is_some() => {
+                let composed = compose(c, next);
+                if composed.chars().nth(1).is_none() {
+                    out.push_str(&composed);
+                    chars.next();
+                } else {
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composes_accented_letters() {
+        assert_eq!(compose('´', 'e'), "é");
+        assert_eq!(compose('^', 'O'), "Ô");
+        assert_eq!(compose('\'', 'a'), "á");
+        assert_eq!(compose('¸', 'c'), "ç");
+        assert_eq!(compose('\u{0308}', 'u'), "ü");
+        // Already composed by the terminal
+        assert_eq!(compose('´', 'é'), "é");
+        // No such letter
+        assert_eq!(compose('¨', 'q'), "¨q");
+        assert_eq!(compose('x', 'e'), "xe");
+    }
+
+    #[test]
+    fn starts_only_letters_with_the_mark() {
+        assert!(starts('´', "é", false));
+        assert!(starts('"', "ü", true));
+        assert!(!starts('"', "ü", false));
+        assert!(!starts('´', "e", false));
+        assert!(!starts('\'', "'", true));
+        assert!(!starts('^', "x", true));
+    }
+
+    #[test]
+    fn composes_dead_keys_in_text() {
+        assert_eq!(compose_text("caf´e"), "café");
+        assert_eq!(compose_text("¨uber it's"), "über it's");
+        assert_eq!(compose_text("a´"), "a´");
+    }
+}
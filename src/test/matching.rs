@@ -0,0 +1,153 @@
+//! How typed input is compared with the words of a test.
+
+use std::collections::BTreeMap;
+use unicode_normalization::UnicodeNormalization;
+
+/// Options for comparing typed input with the words of a test. Both are compared in
+/// Unicode normal form C, so a letter typed as a base letter and a combining accent
+/// matches the same letter as one character.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Matching {
+    pub case_insensitive: bool,
+    /// Accept base letters for letters with diacritics, like `e` for `é`
+    pub ignore_diacritics: bool,
+}
+
+impl Matching {
+    /// `text` in the form it's compared in.
+    pub fn fold(&self, text: &str) -> String {
+        let folded = if self.ignore_diacritics {
+            strip_diacritics(text)
+        } else {
+            normalize(text)
+        };
+        if self.case_insensitive {
+            folded.to_lowercase()
+        } else {
+            folded
+        }
+    }
+
+    /// Whether `typed` matches all of `text`.
+    pub fn matches(&self, typed: &str, text: &str) -> bool {
+        self.fold(typed) == self.fold(text)
+    }
+
+    /// Whether `typed` matches the start of `text`.
+    pub fn is_prefix(&self, typed: &str, text: &str) -> bool {
+        self.fold(text).starts_with(&self.fold(typed))
+    }
+
+    /// Whether the typed grapheme `typed` matches `expected`. With `partial`, a grapheme
+    /// that's still being typed, like a Thai consonant before its vowel mark, matches
+    /// the start of `expected`.
+    pub fn graphemes_match(&self, typed: &str, expected: &str, partial: bool) -> bool {
+        typed == expected
+            || if partial {
+                self.is_prefix(typed, expected)
+            } else {
+                self.matches(typed, expected)
+            }
+    }
+}
+
+/// `text` in Unicode normal form C, with letters and their accents composed.
+pub fn normalize(text: &str) -> String {
+    text.nfc().collect()
+}
+
+/// `text` with the accents and other diacritics of Latin, Greek and Cyrillic letters
+/// removed, so `Ångström` becomes `Angstrom`. Marks of other scripts, like Thai vowels,
+/// are part of their letters and are kept.
+pub fn strip_diacritics(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !('\u{0300}'..='\u{036F}').contains(c))
+        .map(|c| match c {
+            'ø' => 'o',
+            'Ø' => 'O',
+            'ł' => 'l',
+            'Ł' => 'L',
+            'đ' => 'd',
+            'Đ' => 'D',
+            'ħ' => 'h',
+            'Ħ' => 'H',
+            'ı' => 'i',
+            c => c,
+        })
+        .nfc()
+        .collect()
+}
+
+/// Replace each key of `table` in `text` with its value, preferring longer keys.
+pub fn substitute(text: &str, table: &BTreeMap<String, String>) -> String {
+    if table.is_empty() {
+        return text.to_owned();
+    }
+    let mut keys: Vec<(&str, &str)> = table
+        .iter()
+        .filter(|(from, _)| !from.is_empty())
+        .map(|(from, to)| (from.as_str(), to.as_str()))
+        .collect();
+    keys.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match keys.iter().find(|(from, _)| rest.starts_with(from)) {
+            Some((from, to)) => {
+                out.push_str(to);
+                rest = &rest[from.len()..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composes_decomposed_letters() {
+        let matching = Matching::default();
+        assert!(matching.matches("cafe\u{301}", "café"));
+        assert!(!matching.matches("cafe", "café"));
+        assert_eq!(normalize("cafe\u{301}").chars().count(), 4);
+
+        // The consonant of "กิ" is typed before its vowel mark
+        assert!(matching.graphemes_match("ก", "กิ", true));
+        assert!(!matching.graphemes_match("ก", "กิ", false));
+    }
+
+    #[test]
+    fn ignores_diacritics_when_asked() {
+        let matching = Matching {
+            case_insensitive: true,
+            ignore_diacritics: true,
+        };
+        assert!(matching.matches("Francais", "français"));
+        assert!(matching.matches("uber", "Über"));
+        assert!(matching.is_prefix("Kobenh", "København"));
+        assert!(matching.graphemes_match("c", "ç", false));
+        assert!(matching.graphemes_match("ç", "ç", false));
+        assert!(!matching.graphemes_match("s", "ß", false));
+        // Thai vowel marks are letters, not diacritics
+        assert_eq!(strip_diacritics("กิน"), "กิน");
+    }
+
+    #[test]
+    fn substitutes_longest_keys_first() {
+        let table: BTreeMap<String, String> =
+            [("ß", "ss"), ("\u{2019}", "'"), ("ae", "æ"), ("a", "A")]
+                .into_iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect();
+        assert_eq!(substitute("Straße", &table), "StrAsse");
+        assert_eq!(substitute("it\u{2019}s aeon", &table), "it's æon");
+        assert_eq!(substitute("Straße", &BTreeMap::new()), "Straße");
+    }
+}
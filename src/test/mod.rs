@@ -1,11 +1,16 @@
+mod compose;
 #[cfg(test)]
 pub mod helpers;
+pub mod matching;
 pub mod results;
 
+use crate::config::{KeyBindings, KeyMap};
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
-use std::collections::HashMap;
+use matching::Matching;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct TestEvent {
     pub time: Instant,
@@ -27,11 +32,22 @@ impl fmt::Debug for TestEvent {
     }
 }
 
+/// Where the prompt starts a new line after a word, for texts that keep their layout.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Break {
+    #[default]
+    None,
+    Line,
+    /// A new line after an empty one
+    Paragraph,
+}
+
 #[derive(Debug)]
 pub struct TestWord {
     pub text: String,
     pub progress: String,
     pub events: Vec<TestEvent>,
+    pub break_after: Break,
 }
 
 impl From<String> for TestWord {
@@ -40,16 +56,50 @@ impl From<String> for TestWord {
             text: string,
             progress: String::new(),
             events: Vec::new(),
+            break_after: Break::None,
         }
     }
 }
 
+impl TestWord {
+    /// The grapheme of the text after those typed so far, if any.
+    fn next_grapheme(&self) -> Option<&str> {
+        self.text
+            .graphemes(true)
+            .nth(self.progress.graphemes(true).count())
+    }
+
+    /// Delete the last typed grapheme, so a letter goes together with its marks.
+    fn delete_grapheme(&mut self) {
+        let end = self
+            .progress
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i);
+        self.progress.truncate(end);
+    }
+}
+
 impl From<&str> for TestWord {
     fn from(string: &str) -> Self {
         Self::from(string.to_string())
     }
 }
 
+/// Bindings for the editing keys handled by the test itself.
+#[derive(Debug, Clone)]
+pub struct EditKeys {
+    pub submit_word: KeyBindings,
+    pub delete_char: KeyBindings,
+    pub delete_word: KeyBindings,
+}
+
+impl Default for EditKeys {
+    fn default() -> Self {
+        KeyMap::default().edit_keys()
+    }
+}
+
 #[derive(Debug)]
 pub struct Test {
     pub words: Vec<TestWord>,
@@ -58,9 +108,18 @@ pub struct Test {
     pub backtracking_enabled: bool,
     pub sudden_death_enabled: bool,
     pub case_insensitive: bool,
+    pub ignore_diacritics: bool,
     pub no_backspace: bool,
     pub look_ahead: Option<usize>,
+    pub keys: EditKeys,
+    /// Dead key waiting for the letter it accents
+    pub preedit: Option<char>,
+    /// Whether ASCII quotes and accents are dead keys too
+    ascii_dead_keys: bool,
+    /// Replacements for the words and typed input, like `ss` for `ß`
+    substitutions: BTreeMap<String, String>,
     pending_presses: HashMap<KeyCode, (usize, usize)>,
+    paused_at: Option<Instant>,
 }
 
 impl Test {
@@ -73,15 +132,94 @@ impl Test {
         look_ahead: Option<usize>,
     ) -> Self {
         Self {
-            words: words.into_iter().map(TestWord::from).collect(),
+            words: words
+                .iter()
+                .map(|word| TestWord::from(matching::normalize(word)))
+                .collect(),
             current_word: 0,
             complete: false,
             backtracking_enabled,
             sudden_death_enabled,
             case_insensitive,
+            ignore_diacritics: false,
             no_backspace,
             look_ahead,
+            keys: EditKeys::default(),
+            preedit: None,
+            ascii_dead_keys: false,
+            substitutions: BTreeMap::new(),
             pending_presses: HashMap::new(),
+            paused_at: None,
+        }
+    }
+
+    /// Use `keys` instead of the default editing keys.
+    pub fn with_keys(mut self, keys: EditKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Accept base letters for letters with diacritics, like `e` for `é`.
+    pub fn with_ignore_diacritics(mut self, ignore_diacritics: bool) -> Self {
+        self.ignore_diacritics = ignore_diacritics;
+        self
+    }
+
+    /// Hold ASCII quotes and accents typed before a letter they accent, like the dead
+    /// keys of US-International layouts.
+    pub fn with_ascii_dead_keys(mut self, ascii_dead_keys: bool) -> Self {
+        self.ascii_dead_keys = ascii_dead_keys;
+        self
+    }
+
+    /// Replace the keys of `table` with their values in the words and in typed input.
+    pub fn with_substitutions(mut self, table: &BTreeMap<String, String>) -> Self {
+        for word in &mut self.words {
+            word.text = matching::normalize(&matching::substitute(&word.text, table));
+        }
+        self.substitutions = table.clone();
+        self
+    }
+
+    /// How typed input is compared with the words.
+    pub fn matching(&self) -> Matching {
+        Matching {
+            case_insensitive: self.case_insensitive,
+            ignore_diacritics: self.ignore_diacritics,
+        }
+    }
+
+    /// Break the prompt after words as given by `breaks`, one for each word.
+    pub fn with_breaks(mut self, breaks: &[Break]) -> Self {
+        for (word, &b) in self.words.iter_mut().zip(breaks) {
+            word.break_after = b;
+        }
+        self
+    }
+
+    /// Breaks after each word, to start the same test again.
+    pub fn breaks(&self) -> Vec<Break> {
+        self.words.iter().map(|w| w.break_after).collect()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Pause or resume the test. Time spent paused doesn't count: on resume, all
+    /// recorded events are moved forward by the length of the pause.
+    pub fn toggle_pause(&mut self) {
+        match self.paused_at.take() {
+            Some(start) => {
+                let paused = start.elapsed();
+                for event in self.words.iter_mut().flat_map(|w| w.events.iter_mut()) {
+                    event.time += paused;
+                    if let Some(release) = event.release_time.as_mut() {
+                        *release += paused;
+                    }
+                }
+            }
+            None => self.paused_at = Some(Instant::now()),
         }
     }
 
@@ -90,94 +228,46 @@ impl Test {
             self.record_release(key.code);
             return;
         }
-        if key.kind != KeyEventKind::Press {
+        if key.kind != KeyEventKind::Press || self.is_paused() {
             return;
         }
 
         let word_idx = self.current_word;
         let events_before = self.words[word_idx].events.len();
+        let matching = self.matching();
 
-        let word = &mut self.words[self.current_word];
         match key.code {
-            KeyCode::Char(' ') | KeyCode::Enter
-                if !key.modifiers.contains(KeyModifiers::CONTROL) =>
-            {
-                if word.text.chars().nth(word.progress.len()) == Some(' ') {
-                    word.progress.push(' ');
-                    word.events.push(TestEvent {
-                        time: Instant::now(),
-                        correct: Some(true),
-                        key,
-                        release_time: None,
-                    })
-                } else if !word.progress.is_empty() || word.text.is_empty() {
-                    let correct = if self.case_insensitive {
-                        word.text.to_lowercase() == word.progress.to_lowercase()
-                    } else {
-                        word.text == word.progress
-                    };
-                    if self.sudden_death_enabled && !correct {
-                        self.reset();
-                    } else {
-                        word.events.push(TestEvent {
-                            time: Instant::now(),
-                            correct: Some(correct),
-                            key,
-                            release_time: None,
-                        });
-                        self.next_word();
-                    }
+            _ if self.keys.submit_word.matches(key.code, key.modifiers) => {
+                // A dead key followed by space types the accent itself
+                match self.preedit.take() {
+                    Some(dead) => self.insert(dead.encode_utf8(&mut [0; 4]), key),
+                    None => self.submit_word(key),
                 }
             }
-            KeyCode::Backspace if !self.no_backspace => {
-                if word.progress.is_empty() && self.backtracking_enabled {
-                    self.last_word();
-                } else {
-                    let is_error = if self.case_insensitive {
-                        !word
-                            .text
-                            .to_lowercase()
-                            .starts_with(&word.progress.to_lowercase())
-                    } else {
-                        !word.text.starts_with(&word.progress[..])
-                    };
-                    word.events.push(TestEvent {
-                        time: Instant::now(),
-                        correct: Some(is_error),
-                        key,
-                        release_time: None,
-                    });
-                    word.progress.pop();
+            _ if self.keys.delete_char.matches(key.code, key.modifiers) => {
+                // Cancelling a dead key isn't a correction
+                if self.preedit.take().is_some() || self.no_backspace {
+                    return;
                 }
-            }
-            // CTRL-H → delete single character (same as Backspace)
-            KeyCode::Char('h')
-                if key.modifiers.contains(KeyModifiers::CONTROL) && !self.no_backspace =>
-            {
+                let word = &mut self.words[self.current_word];
                 if word.progress.is_empty() && self.backtracking_enabled {
                     self.last_word();
                 } else {
-                    let is_error = if self.case_insensitive {
-                        !word
-                            .text
-                            .to_lowercase()
-                            .starts_with(&word.progress.to_lowercase())
-                    } else {
-                        !word.text.starts_with(&word.progress[..])
-                    };
+                    let is_error = !matching.is_prefix(&word.progress, &word.text);
                     word.events.push(TestEvent {
                         time: Instant::now(),
                         correct: Some(is_error),
                         key,
                         release_time: None,
                     });
-                    word.progress.pop();
+                    word.delete_grapheme();
                 }
             }
-            // CTRL-W and CTRL-Backspace → delete entire word
-            KeyCode::Char('w')
-                if key.modifiers.contains(KeyModifiers::CONTROL) && !self.no_backspace =>
-            {
+            _ if self.keys.delete_word.matches(key.code, key.modifiers) => {
+                if self.no_backspace {
+                    return;
+                }
+                self.preedit = None;
                 if self.words[self.current_word].progress.is_empty() {
                     self.last_word();
                 }
@@ -193,37 +283,30 @@ impl Test {
                 word.progress.clear();
             }
             KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                let ch = if self.case_insensitive {
-                    c.to_lowercase().next().unwrap_or(c)
-                } else {
-                    c
-                };
-                word.progress.push(ch);
-                let correct = if self.case_insensitive {
-                    word.text
-                        .to_lowercase()
-                        .starts_with(&word.progress.to_lowercase())
-                } else {
-                    word.text.starts_with(&word.progress[..])
-                };
-                if self.sudden_death_enabled && !correct {
-                    self.reset();
-                } else {
-                    word.events.push(TestEvent {
-                        time: Instant::now(),
-                        correct: Some(correct),
-                        key,
-                        release_time: None,
-                    });
-                    let words_match = if self.case_insensitive {
-                        word.progress.to_lowercase() == word.text.to_lowercase()
-                    } else {
-                        word.progress == word.text
-                    };
-                    if words_match && self.current_word == self.words.len() - 1 {
-                        self.complete = true;
-                        self.current_word = 0;
+                // A dead key is held until its letter comes, and the two count as one
+                // keystroke
+                let word = &self.words[self.current_word];
+                if self.preedit.is_none()
+                    && word
+                        .next_grapheme()
+                        .is_some_and(|next| compose::starts(c, next, self.ascii_dead_keys))
+                {
+                    self.preedit = Some(c);
+                    return;
+                }
+                match self.preedit.take() {
+                    Some(dead) => {
+                        let text = compose::compose(dead, c);
+                        let last = text.chars().next_back().unwrap_or(c);
+                        self.insert(
+                            &text,
+                            KeyEvent {
+                                code: KeyCode::Char(last),
+                                ..key
+                            },
+                        );
                     }
+                    None => self.insert(c.encode_utf8(&mut [0; 4]), key),
                 }
             }
             _ => {}
@@ -240,11 +323,161 @@ impl Test {
         }
     }
 
+    /// Handle text that arrives at once, like a paste or what an input method commits.
+    /// Each grapheme of it counts as a keystroke, timed as if typed evenly since the
+    /// keystroke before, and whitespace submits words.
+    pub fn handle_text(&mut self, text: &str) {
+        if self.is_paused() {
+            return;
+        }
+        let text = match (self.preedit.take(), text.chars().next()) {
+            (Some(dead), Some(c)) => compose::compose(dead, c) + &text[c.len_utf8()..],
+            _ => text.to_owned(),
+        };
+        for piece in compose::compose_text(&text).split_inclusive(char::is_whitespace) {
+            let unit = piece.trim_end_matches(char::is_whitespace);
+            let start = self.last_event_time();
+            let (word_idx, events_before) = (
+                self.current_word,
+                self.words[self.current_word].events.len(),
+            );
+            for grapheme in unit.graphemes(true) {
+                // Keyed by its letter, as a mark on its own would join the one before
+                let first = grapheme.chars().next().unwrap_or(' ');
+                self.insert(
+                    grapheme,
+                    KeyEvent::new(KeyCode::Char(first), KeyModifiers::NONE),
+                );
+                if self.complete {
+                    break;
+                }
+            }
+            if let Some(start) = start {
+                spread_times(&mut self.words[word_idx].events, events_before, start);
+            }
+            if unit.len() < piece.len() && !self.complete {
+                self.submit_word(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+            }
+            if self.complete {
+                break;
+            }
+        }
+    }
+
+    /// Time of the last keystroke of the test so far.
+    fn last_event_time(&self) -> Option<Instant> {
+        self.words[..=self.current_word]
+            .iter()
+            .rev()
+            .find_map(|word| word.events.last())
+            .map(|event| event.time)
+    }
+
+    /// Handle key presses that were waiting to be read together: text an input method
+    /// committed at once, or fast typing that piled up while the screen was drawn. If
+    /// they continue the test correctly, the keys are handled one by one as typed.
+    /// Otherwise they're handled as text.
+    pub fn handle_burst(&mut self, keys: &[KeyEvent]) {
+        let text: String = keys
+            .iter()
+            .filter_map(|key| match key.code {
+                KeyCode::Char(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        if self.types_one_by_one(&text) {
+            for &key in keys {
+                self.handle_key(key);
+            }
+        } else {
+            self.handle_text(&text);
+        }
+    }
+
+    /// Whether `text` continues the test correctly from what's typed so far, with spaces
+    /// between words. Then each of its characters types the test on its own, even the
+    /// marks of a Thai or Devanagari letter typed one after another.
+    fn types_one_by_one(&self, text: &str) -> bool {
+        if self.preedit.is_some() {
+            return false;
+        }
+        let word = &self.words[self.current_word];
+        let mut expected = word.text.clone();
+        for next in &self.words[self.current_word + 1..] {
+            expected.push(' ');
+            expected.push_str(&next.text);
+        }
+        let typed = word.progress.clone() + &matching::substitute(text, &self.substitutions);
+        self.matching()
+            .is_prefix(&matching::normalize(&typed), &expected)
+    }
+
+    /// Add `text` to the current word as one keystroke: a typed character, or a unit
+    /// made of several, like a letter with its dead key accent.
+    fn insert(&mut self, text: &str, key: KeyEvent) {
+        let matching = self.matching();
+        let text = if self.case_insensitive {
+            text.to_lowercase()
+        } else {
+            text.to_owned()
+        };
+        let word = &mut self.words[self.current_word];
+        word.progress
+            .push_str(&matching::substitute(&text, &self.substitutions));
+        word.progress = matching::normalize(&word.progress);
+        let correct = matching.is_prefix(&word.progress, &word.text);
+        if self.sudden_death_enabled && !correct {
+            self.reset();
+        } else {
+            word.events.push(TestEvent {
+                time: Instant::now(),
+                correct: Some(correct),
+                key,
+                release_time: None,
+            });
+            let words_match = matching.matches(&word.progress, &word.text);
+            if words_match && self.current_word == self.words.len() - 1 {
+                self.complete = true;
+                self.current_word = 0;
+            }
+        }
+    }
+
+    /// Submit the current word, or type the space when the word has one next.
+    fn submit_word(&mut self, key: KeyEvent) {
+        let matching = self.matching();
+        let word = &mut self.words[self.current_word];
+        if word.next_grapheme() == Some(" ") {
+            word.progress.push(' ');
+            word.events.push(TestEvent {
+                time: Instant::now(),
+                correct: Some(true),
+                key,
+                release_time: None,
+            })
+        } else if !word.progress.is_empty() || word.text.is_empty() {
+            let correct = matching.matches(&word.progress, &word.text);
+            if self.sudden_death_enabled && !correct {
+                self.reset();
+            } else {
+                word.events.push(TestEvent {
+                    time: Instant::now(),
+                    correct: Some(correct),
+                    key,
+                    release_time: None,
+                });
+                self.next_word();
+            }
+        }
+    }
+
     fn record_release(&mut self, code: KeyCode) {
+        // Releases during a pause count as happening when the pause started
+        let now = self.paused_at.unwrap_or_else(Instant::now);
         if let Some((word_idx, event_idx)) = self.pending_presses.remove(&code) {
             if let Some(word) = self.words.get_mut(word_idx) {
                 if let Some(event) = word.events.get_mut(event_idx) {
-                    event.release_time = Some(Instant::now());
+                    event.release_time = Some(now);
                 }
             }
         }
@@ -272,10 +505,29 @@ impl Test {
         });
         self.current_word = 0;
         self.complete = false;
+        self.preedit = None;
         self.pending_presses.clear();
     }
 }
 
+/// Spread the times of `events[from..]`, recorded together, evenly over the time since
+/// `start`, so each counts as typed at the same speed.
+fn spread_times(events: &mut [TestEvent], from: usize, start: Instant) {
+    let Some(added) = events.get_mut(from..) else {
+        return;
+    };
+    let Some(total) = added
+        .last()
+        .and_then(|last| last.time.checked_duration_since(start))
+    else {
+        return;
+    };
+    let count = added.len() as f64;
+    for (i, event) in added.iter_mut().enumerate() {
+        event.time = start + total.mul_f64((i + 1) as f64 / count);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::helpers::*;
@@ -528,6 +780,229 @@ mod tests {
         );
     }
 
+    #[test]
+    fn combining_accents_compose_with_the_letter_before() {
+        let mut test = default_test(vec!["cafe\u{301}".to_string()]);
+        assert_eq!(test.words[0].text, "café");
+        type_string(&mut test, "cafe\u{301}");
+        assert!(test.complete, "A typed accent should complete 'é'");
+    }
+
+    #[test]
+    fn ignore_diacritics_accepts_base_letters() {
+        let mut test =
+            default_test(vec!["ça".to_string(), "va".to_string()]).with_ignore_diacritics(true);
+        type_string(&mut test, "ca va");
+        assert!(test.complete);
+        assert_eq!(test.words[0].events[0].correct, Some(true));
+
+        let mut test = default_test(vec!["ça".to_string()]);
+        type_string(&mut test, "c");
+        assert_eq!(
+            test.words[0].events[0].correct,
+            Some(false),
+            "Diacritics count unless they're ignored"
+        );
+    }
+
+    #[test]
+    fn substitutions_replace_words_and_input() {
+        let table = [("ß".to_string(), "ss".to_string())].into_iter().collect();
+        let test = default_test(vec!["Straße".to_string()]).with_substitutions(&table);
+        assert_eq!(test.words[0].text, "Strasse");
+
+        for typed in ["Strasse", "Straße"] {
+            let mut test = default_test(vec!["Straße".to_string()]).with_substitutions(&table);
+            type_string(&mut test, typed);
+            assert!(test.complete, "Typing '{}' should complete the test", typed);
+        }
+    }
+
+    fn thai_words(count: usize) -> Vec<String> {
+        let thai = include_str!("../../resources/runtime/language/thai");
+        let mut words = crate::language::Language::parse(thai).unwrap().words;
+        words.truncate(count);
+        words
+    }
+
+    #[test]
+    fn thai_words_are_typed_correctly_one_character_at_a_time() {
+        let words = thai_words(200);
+        let mut test = default_test(words.clone());
+        type_string(&mut test, &words.join(" "));
+        assert!(test.complete);
+        for word in &test.words {
+            assert!(
+                word.events.iter().all(|e| e.correct == Some(true)),
+                "{} has an incorrect keystroke",
+                word.text
+            );
+        }
+    }
+
+    #[test]
+    fn backspace_deletes_a_whole_grapheme() {
+        // "ที่" is a consonant with a vowel and a tone mark: one grapheme, three chars
+        let mut test = default_test(vec!["ที่นี่".to_string()]);
+        type_string(&mut test, "ที่น");
+        test.handle_key(press(KeyCode::Backspace));
+        assert_eq!(test.words[0].progress, "ที่");
+        test.handle_key(press(KeyCode::Backspace));
+        assert_eq!(test.words[0].progress, "");
+
+        let mut test = default_test(vec!["👍🏽ok".to_string()]);
+        type_string(&mut test, "👍🏽");
+        test.handle_key(press(KeyCode::Backspace));
+        assert_eq!(
+            test.words[0].progress, "",
+            "The skin tone goes with the emoji"
+        );
+    }
+
+    #[test]
+    fn dead_key_and_letter_are_one_keystroke() {
+        let mut test = default_test(vec!["café".to_string()]);
+        type_string(&mut test, "caf´");
+        assert_eq!(test.preedit, Some('´'));
+        assert_eq!(
+            test.words[0].events.len(),
+            3,
+            "The dead key waits for its letter"
+        );
+        type_string(&mut test, "e");
+        assert!(test.complete);
+        assert_eq!(test.words[0].progress, "café");
+        assert_eq!(test.words[0].events.len(), 4);
+        assert!(test.words[0].events.iter().all(|e| e.correct == Some(true)));
+        assert_eq!(test.words[0].events[3].key.code, KeyCode::Char('é'));
+
+        // Sent together by the terminal
+        let mut test = default_test(vec!["é".to_string()]);
+        test.handle_text("´e");
+        assert!(test.complete);
+        assert_eq!(test.words[0].events.len(), 1);
+    }
+
+    #[test]
+    fn dead_key_passed_on_with_the_composed_letter_is_dropped() {
+        let mut test = default_test(vec!["ça".to_string()]);
+        type_string(&mut test, "¸ça");
+        assert!(test.complete);
+        assert_eq!(test.words[0].events.len(), 2);
+        assert!(test.words[0].events.iter().all(|e| e.correct == Some(true)));
+    }
+
+    #[test]
+    fn backspace_cancels_a_dead_key_without_a_correction() {
+        let mut test = default_test(vec!["é".to_string()]);
+        type_string(&mut test, "´");
+        test.handle_key(press(KeyCode::Backspace));
+        assert_eq!(test.preedit, None);
+        assert!(test.words[0].events.is_empty());
+
+        // A dead key and space type the accent itself
+        type_string(&mut test, "´ ");
+        assert_eq!(test.words[0].progress, "´");
+        assert_eq!(test.words[0].events[0].correct, Some(false));
+    }
+
+    #[test]
+    fn ascii_dead_keys_only_when_enabled() {
+        // A stray quote before an accented letter is a mistake, not an accent
+        let mut test = default_test(vec!["é".to_string()]);
+        type_string(&mut test, "'");
+        assert_eq!(test.preedit, None);
+        assert_eq!(test.words[0].progress, "'");
+        assert_eq!(test.words[0].events.len(), 1);
+        assert_eq!(test.words[0].events[0].correct, Some(false));
+
+        let mut test = default_test(vec!["é".to_string()]).with_ascii_dead_keys(true);
+        type_string(&mut test, "'e");
+        assert!(test.complete);
+        assert_eq!(test.words[0].events.len(), 1);
+    }
+
+    #[test]
+    fn committed_text_is_one_keystroke_per_grapheme() {
+        let mut test = default_test(vec!["日本語".to_string(), "です".to_string()]);
+        test.handle_key(press(KeyCode::Char('x')));
+        test.handle_key(press(KeyCode::Backspace));
+        let start = test.words[0].events[1].time;
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        test.handle_text("日本");
+        assert_eq!(test.words[0].progress, "日本");
+        let events = &test.words[0].events[2..];
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.correct == Some(true)));
+        assert_eq!(events[1].key.code, KeyCode::Char('本'));
+        // Timed as if typed evenly since the keystroke before
+        let gap = events[1].time.duration_since(events[0].time);
+        assert!(events[0].time > start);
+        assert!(gap >= std::time::Duration::from_millis(10), "{:?}", gap);
+
+        test.handle_text("語 です");
+        assert!(test.complete);
+        assert_eq!(
+            test.words[0].events.len(),
+            6,
+            "Three graphemes and the space"
+        );
+        assert_eq!(test.words[1].events.len(), 2);
+
+        // A wrong grapheme is counted with its own key
+        let mut test = default_test(vec!["กับ".to_string()]);
+        test.handle_text("กีบ");
+        let events = &test.words[0].events;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].key.code, KeyCode::Char('ก'));
+        assert_eq!(events[0].correct, Some(false));
+    }
+
+    #[test]
+    fn queued_cyrillic_keys_stay_separate_keystrokes() {
+        let mut test = default_test(vec!["привет".to_string(), "мир".to_string()]);
+        let keys: Vec<KeyEvent> = "привет ми"
+            .chars()
+            .map(|c| press(KeyCode::Char(c)))
+            .collect();
+        test.handle_burst(&keys);
+        assert_eq!(test.current_word, 1);
+        assert_eq!(test.words[0].events.len(), 7, "Six letters and the space");
+        assert_eq!(test.words[1].progress, "ми");
+        assert_eq!(test.words[1].events.len(), 2);
+
+        // Thai marks typed after their letters are keys of their own too
+        let mut test = default_test(vec!["กับ".to_string()]);
+        let keys: Vec<KeyEvent> = "กั".chars().map(|c| press(KeyCode::Char(c))).collect();
+        test.handle_burst(&keys);
+        assert_eq!(test.words[0].events.len(), 2);
+        assert!(test.words[0].events.iter().all(|e| e.correct == Some(true)));
+
+        // Text that doesn't type the test is still counted per grapheme, keeping the
+        // wrong key
+        let mut test = default_test(vec!["привет".to_string()]);
+        let keys: Vec<KeyEvent> = "прив´е".chars().map(|c| press(KeyCode::Char(c))).collect();
+        test.handle_burst(&keys);
+        let events = &test.words[0].events;
+        assert_eq!(events.len(), 6);
+        assert!(events[..4].iter().all(|e| e.correct == Some(true)));
+        assert_eq!(events[4].key.code, KeyCode::Char('´'));
+        assert_eq!(events[4].correct, Some(false));
+    }
+
+    #[test]
+    fn pasted_text_submits_words() {
+        let mut test = default_test(vec![
+            "one".to_string(),
+            "two".to_string(),
+            "three".to_string(),
+        ]);
+        test.handle_text("one tw0\n");
+        assert_eq!(test.current_word, 2);
+        assert_eq!(test.words[0].events.last().unwrap().correct, Some(true));
+        assert_eq!(test.words[1].events.last().unwrap().correct, Some(false));
+    }
+
     #[test]
     fn no_backspace_blocks_backspace() {
         let mut test = no_backspace_test(vec!["hello".to_string()]);
@@ -616,4 +1091,56 @@ mod tests {
             "Should complete test with look_ahead enabled"
         );
     }
+
+    #[test]
+    fn custom_edit_keys() {
+        let key_map: KeyMap = toml::from_str(
+            r#"
+submit_word = "Enter"
+delete_char = "C-b"
+delete_word = "A-Backspace"
+"#,
+        )
+        .unwrap();
+        let mut test =
+            default_test(vec!["ab".to_string(), "cd".to_string()]).with_keys(key_map.edit_keys());
+
+        type_string(&mut test, "ax");
+        test.handle_key(press_ctrl(KeyCode::Char('b')));
+        assert_eq!(test.words[0].progress, "a");
+        test.handle_key(press(KeyCode::Backspace));
+        assert_eq!(test.words[0].progress, "a", "Backspace is no longer bound");
+
+        test.handle_key(KeyEvent {
+            modifiers: KeyModifiers::ALT,
+            ..press(KeyCode::Backspace)
+        });
+        assert_eq!(test.words[0].progress, "");
+
+        type_string(&mut test, "ab");
+        test.handle_key(press(KeyCode::Enter));
+        assert_eq!(test.current_word, 1);
+    }
+
+    #[test]
+    fn pause_ignores_keys_and_excludes_paused_time() {
+        let mut test = default_test(vec!["ab".to_string()]);
+        type_string(&mut test, "a");
+        let first = test.words[0].events[0].time;
+
+        test.toggle_pause();
+        assert!(test.is_paused());
+        type_string(&mut test, "b");
+        assert_eq!(
+            test.words[0].progress, "a",
+            "Typing is ignored while paused"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        test.toggle_pause();
+
+        assert!(!test.is_paused());
+        assert!(test.words[0].events[0].time >= first + std::time::Duration::from_millis(20));
+        type_string(&mut test, "b");
+        assert!(test.complete);
+    }
 }
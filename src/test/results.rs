@@ -3,6 +3,7 @@ use super::{is_missed_word_event, Test};
 use crossterm::event::{KeyCode, KeyEvent};
 use std::collections::{HashMap, HashSet};
 use std::{cmp, fmt};
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Fraction {
@@ -62,6 +63,10 @@ pub struct DwellData {
 }
 
 pub struct Results {
+    /// Set by the caller when saving these results to history failed.
+    pub save_error: Option<String>,
+    /// Set by the caller for tests that can be taken again with `--code`.
+    pub code: Option<String>,
     pub timing: TimingData,
     pub accuracy: AccuracyData,
     pub dwell: DwellData,
@@ -83,6 +88,8 @@ impl From<&Test> for Results {
             .collect();
 
         Self {
+            save_error: None,
+            code: None,
             timing: calc_timing(&events),
             accuracy: calc_accuracy(&events, &target_chars),
             dwell: calc_dwell(&events),
@@ -93,6 +100,36 @@ impl From<&Test> for Results {
     }
 }
 
+/// Whether `event` typed a character that joins the grapheme typed before it, like a
+/// Thai vowel mark or an emoji skin tone, instead of starting a new one.
+fn continues_grapheme(event: &super::TestEvent) -> bool {
+    match event.key.code {
+        KeyCode::Char(c) => {
+            let mut text = String::from("a");
+            text.push(c);
+            text.graphemes(true).count() == 1
+        }
+        _ => false,
+    }
+}
+
+/// Split `events` into the keystrokes of each grapheme typed. Accuracy and speed count
+/// graphemes, so a letter typed with its marks counts once.
+fn grapheme_groups<'a, 'b>(events: &'b [&'a super::TestEvent]) -> Vec<&'b [&'a super::TestEvent]> {
+    let mut groups: Vec<&[&super::TestEvent]> = Vec::new();
+    let mut start = 0;
+    for i in 1..=events.len() {
+        let joins = i < events.len()
+            && matches!(events[i - 1].key.code, KeyCode::Char(_))
+            && continues_grapheme(events[i]);
+        if !joins {
+            groups.push(&events[start..i]);
+            start = i;
+        }
+    }
+    groups
+}
+
 fn calc_timing(events: &[&super::TestEvent]) -> TimingData {
     let mut timing = TimingData {
         overall_cps: -1.0,
@@ -110,14 +147,23 @@ fn calc_timing(events: &[&super::TestEvent]) -> TimingData {
             .map(|d| d.as_secs_f64());
 
         if let Some(event_dur) = event_dur {
-            timing.per_event.push(event_dur);
-
             let key = keys.entry(win[1].key).or_insert((0.0, 0));
             key.0 += event_dur;
             key.1 += 1;
         }
     }
 
+    // A grapheme is typed when its last keystroke is
+    let ends: Vec<&super::TestEvent> = grapheme_groups(events)
+        .iter()
+        .filter_map(|group| group.last().copied())
+        .collect();
+    for win in ends.windows(2) {
+        if let Some(dur) = win[1].time.checked_duration_since(win[0].time) {
+            timing.per_event.push(dur.as_secs_f64());
+        }
+    }
+
     timing.per_key = keys
         .into_iter()
         .map(|(key, (total, count))| (key, total / count as f64))
@@ -134,35 +180,41 @@ fn calc_accuracy(events: &[&super::TestEvent], target_chars: &HashSet<char>) ->
         per_key: HashMap::new(),
     };
 
-    events
+    let scored: Vec<&super::TestEvent> = events
         .iter()
+        .copied()
         .filter(|event| event.correct.is_some())
-        .for_each(|event| {
-            acc.overall.denominator += 1;
-            if event.correct.unwrap() {
-                acc.overall.numerator += 1;
-            }
+        .collect();
 
-            // Only track per-key accuracy for characters that appear in the target text.
-            // Keys not in the target (e.g. typing 'x' when only 'abc' are expected) would
-            // always show 0% accuracy, which is misleading.
-            let in_target = match event.key.code {
-                KeyCode::Char(c) => target_chars.contains(&c),
-                _ => true,
-            };
-
-            if in_target {
-                let key = acc
-                    .per_key
-                    .entry(event.key)
-                    .or_insert_with(|| Fraction::new(0, 0));
-
-                key.denominator += 1;
-                if event.correct.unwrap() {
-                    key.numerator += 1;
-                }
+    // Overall accuracy counts graphemes: one is right if all its keystrokes are
+    for group in grapheme_groups(&scored) {
+        acc.overall.denominator += 1;
+        if group.iter().all(|event| event.correct == Some(true)) {
+            acc.overall.numerator += 1;
+        }
+    }
+
+    scored.iter().for_each(|event| {
+        // Only track per-key accuracy for characters that appear in the target text.
+        // Keys not in the target (e.g. typing 'x' when only 'abc' are expected) would
+        // always show 0% accuracy, which is misleading.
+        let in_target = match event.key.code {
+            KeyCode::Char(c) => target_chars.contains(&c),
+            _ => true,
+        };
+
+        if in_target {
+            let key = acc
+                .per_key
+                .entry(event.key)
+                .or_insert_with(|| Fraction::new(0, 0));
+
+            key.denominator += 1;
+            if event.correct.unwrap() {
+                key.numerator += 1;
             }
-        });
+        }
+    });
 
     acc
 }
@@ -176,7 +228,7 @@ fn calc_missed_words(test: &Test) -> Vec<String> {
 }
 
 /// Returns the 5 slowest correctly-typed words, sorted slowest first.
-/// Speed is measured as time-per-character (duration / word length).
+/// Speed is measured as time-per-character (duration / graphemes in the word).
 /// Words with errors (missed words) are excluded.
 fn calc_slow_words(test: &Test) -> Vec<String> {
     let mut word_speeds: Vec<(&str, f64)> = test
@@ -194,7 +246,7 @@ fn calc_slow_words(test: &Test) -> Vec<String> {
             let first = word.events.first().unwrap().time;
             let last = word.events.last().unwrap().time;
             let duration = last.checked_duration_since(first)?;
-            let time_per_char = duration.as_secs_f64() / word.text.len() as f64;
+            let time_per_char = duration.as_secs_f64() / word.text.graphemes(true).count() as f64;
             Some((word.text.as_str(), time_per_char))
         })
         .collect();
@@ -351,6 +403,26 @@ mod tests {
         assert_eq!(results.accuracy.overall.denominator, 4);
     }
 
+    #[test]
+    fn accuracy_counts_graphemes() {
+        // "กับ" is two graphemes, "กั" and "บ", typed with three keys
+        let mut test = default_test(vec!["กับ".to_string(), "👍🏽".to_string()]);
+        test.words[0].events.push(make_event('ก', true));
+        test.words[0].events.push(make_event('\u{0E31}', false));
+        test.words[0].events.push(make_event('บ', true));
+        test.words[1].events.push(make_event('👍', true));
+        test.words[1].events.push(make_event('🏽', true));
+
+        let results = Results::from(&test);
+
+        // The mark typed wrong makes its whole grapheme wrong
+        assert_eq!(results.accuracy.overall, Fraction::new(2, 3));
+        // Per-key accuracy still counts each key
+        let mark = results.accuracy.per_key.get(&key_for('\u{0E31}')).unwrap();
+        assert_eq!(*mark, Fraction::new(0, 1));
+        assert_eq!(results.accuracy.per_key.len(), 5);
+    }
+
     fn make_timed_event(c: char, correct: bool, time: Instant) -> super::super::TestEvent {
         super::super::TestEvent {
             time,
@@ -374,6 +446,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn timing_counts_graphemes() {
+        let start = Instant::now();
+        let at = |ms| start + std::time::Duration::from_millis(ms);
+        let mut test = default_test(vec!["กับ".to_string()]);
+        test.words[0]
+            .events
+            .push(make_timed_event('ก', true, at(0)));
+        test.words[0]
+            .events
+            .push(make_timed_event('\u{0E31}', true, at(100)));
+        test.words[0]
+            .events
+            .push(make_timed_event('บ', true, at(500)));
+
+        let results = Results::from(&test);
+
+        // The second grapheme is done 400ms after the first, which ends with its mark
+        assert_eq!(results.timing.per_event, [0.4]);
+        assert!((results.timing.overall_cps - 2.5).abs() < 1e-9);
+        let mark = results.timing.per_key[&key_for('\u{0E31}')];
+        assert!((mark - 0.1).abs() < 1e-9);
+    }
+
     #[test]
     fn slow_words_identifies_slowest() {
         let now = Instant::now();
@@ -1,6 +1,6 @@
-use crate::config::{format_keybinding, Config, Theme};
+use crate::config::{format_keybindings, Config, Theme};
 
-use super::test::{results, Test, TestWord};
+use super::test::{matching::Matching, results, Break, Test, TestWord};
 
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -12,6 +12,8 @@ use ratatui::{
     widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Widget, Wrap},
 };
 use results::Fraction;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 // Convert CPS to WPM (clicks per second)
 const WPM_PER_CPS: f64 = 12.0;
@@ -19,6 +21,9 @@ const WPM_PER_CPS: f64 = 12.0;
 // Width of the moving average window for the WPM chart
 const WPM_SMA_WIDTH: usize = 10;
 
+// Base that combining marks without a letter are drawn on
+const MARK_BASE: char = '\u{25CC}';
+
 #[derive(Clone)]
 struct SizedBlock<'a> {
     block: Block<'a>,
@@ -74,6 +79,17 @@ impl Config {
     }
 }
 
+/// A one-line message below the current screen, like a config reload error.
+pub struct StatusLine<'a>(pub &'a str);
+
+impl ThemedWidget for StatusLine<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer, config: &Config) {
+        buf.set_style(area, config.theme.default);
+        let status = Span::styled(self.0, config.theme.prompt_incorrect);
+        buf.set_span(area.x, area.y, &status, area.width);
+    }
+}
+
 impl ThemedWidget for &Test {
     fn render(self, area: Rect, buf: &mut Buffer, config: &Config) {
         let theme = &config.theme;
@@ -86,16 +102,28 @@ impl ThemedWidget for &Test {
             .split(area);
 
         // Sections
+        let input_title = if self.is_paused() {
+            format!(
+                "Input (paused, {} to resume)",
+                format_keybindings(&config.key_map.pause)
+            )
+        } else {
+            "Input".to_string()
+        };
         let input = SizedBlock {
             block: Block::default()
-                .title(Line::from(vec![Span::styled("Input", theme.title)]))
+                .title(Line::from(vec![Span::styled(input_title, theme.title)]))
                 .borders(Borders::ALL)
                 .border_type(theme.border_type)
                 .border_style(theme.input_border),
             area: chunks[0],
         };
         input.draw_inner(
-            &Line::from(self.words[self.current_word].progress.clone()),
+            &Line::from(visible(&format!(
+                "{}{}",
+                self.words[self.current_word].progress,
+                self.preedit.map(String::from).unwrap_or_default()
+            ))),
             buf,
         );
         input.render(buf);
@@ -105,14 +133,14 @@ impl ThemedWidget for &Test {
                 &self.words,
                 self.current_word,
                 theme,
-                self.case_insensitive,
+                self.matching(),
                 self.look_ahead,
             );
 
             let mut lines: Vec<Line> = Vec::new();
             let mut current_line: Vec<Span> = Vec::new();
             let mut current_width = 0;
-            for word in words {
+            for (word, test_word) in words.into_iter().zip(&self.words) {
                 let word_width: usize = word.iter().map(|s| s.width()).sum();
 
                 if current_width + word_width > chunks[1].width as usize - 2 {
@@ -124,6 +152,14 @@ impl ThemedWidget for &Test {
 
                 current_line.extend(word);
                 current_width += word_width;
+
+                if test_word.break_after != Break::None {
+                    lines.push(Line::from(std::mem::take(&mut current_line)));
+                    current_width = 0;
+                    if test_word.break_after == Break::Paragraph {
+                        lines.push(Line::default());
+                    }
+                }
             }
             lines.push(Line::from(current_line));
 
@@ -144,17 +180,17 @@ fn words_to_spans<'a>(
     words: &'a [TestWord],
     current_word: usize,
     theme: &'a Theme,
-    case_insensitive: bool,
+    matching: Matching,
     look_ahead: Option<usize>,
 ) -> Vec<Vec<Span<'a>>> {
     let mut spans = Vec::new();
 
     for word in &words[..current_word] {
-        let parts = split_typed_word(word, case_insensitive);
+        let parts = split_typed_word(word, matching);
         spans.push(word_parts_to_spans(parts, theme));
     }
 
-    let parts_current = split_current_word(&words[current_word], case_insensitive);
+    let parts_current = split_current_word(&words[current_word], matching);
     spans.push(word_parts_to_spans(parts_current, theme));
 
     let visible_end = match look_ahead {
@@ -181,38 +217,30 @@ enum Status {
     Overtyped,
 }
 
-fn split_current_word(word: &TestWord, case_insensitive: bool) -> Vec<(String, Status)> {
+fn split_current_word(word: &TestWord, matching: Matching) -> Vec<(String, Status)> {
     let mut parts = Vec::new();
     let mut cur_string = String::new();
     let mut cur_status = Status::Untyped;
 
-    let mut progress = word.progress.chars();
-    for tc in word.text.chars() {
+    let typed = word.progress.graphemes(true).count();
+    let mut progress = word.progress.graphemes(true);
+    for (i, tg) in word.text.graphemes(true).enumerate() {
         let p = progress.next();
         let status = match p {
             None => Status::CurrentUntyped,
-            Some(c) => {
-                let matches = if case_insensitive {
-                    c.to_lowercase().eq(tc.to_lowercase())
-                } else {
-                    c == tc
-                };
-                if matches {
-                    Status::CurrentCorrect
-                } else {
-                    Status::CurrentIncorrect
-                }
-            }
+            // The last grapheme typed may still be waiting for its marks
+            Some(g) if matching.graphemes_match(g, tg, i + 1 == typed) => Status::CurrentCorrect,
+            Some(_) => Status::CurrentIncorrect,
         };
 
         if status == cur_status {
-            cur_string.push(tc);
+            cur_string.push_str(tg);
         } else {
             if !cur_string.is_empty() {
                 parts.push((cur_string, cur_status));
                 cur_string = String::new();
             }
-            cur_string.push(tc);
+            cur_string.push_str(tg);
             cur_status = status;
 
             // first currentuntyped is cursor
@@ -232,38 +260,28 @@ fn split_current_word(word: &TestWord, case_insensitive: bool) -> Vec<(String, S
     parts
 }
 
-fn split_typed_word(word: &TestWord, case_insensitive: bool) -> Vec<(String, Status)> {
+fn split_typed_word(word: &TestWord, matching: Matching) -> Vec<(String, Status)> {
     let mut parts = Vec::new();
     let mut cur_string = String::new();
     let mut cur_status = Status::Untyped;
 
-    let mut progress = word.progress.chars();
-    for tc in word.text.chars() {
+    let mut progress = word.progress.graphemes(true);
+    for tg in word.text.graphemes(true) {
         let p = progress.next();
         let status = match p {
             None => Status::Untyped,
-            Some(c) => {
-                let matches = if case_insensitive {
-                    c.to_lowercase().eq(tc.to_lowercase())
-                } else {
-                    c == tc
-                };
-                if matches {
-                    Status::Correct
-                } else {
-                    Status::Incorrect
-                }
-            }
+            Some(g) if matching.graphemes_match(g, tg, false) => Status::Correct,
+            Some(_) => Status::Incorrect,
         };
 
         if status == cur_status {
-            cur_string.push(tc);
+            cur_string.push_str(tg);
         } else {
             if !cur_string.is_empty() {
                 parts.push((cur_string, cur_status));
                 cur_string = String::new();
             }
-            cur_string.push(tc);
+            cur_string.push_str(tg);
             cur_status = status;
         }
     }
@@ -292,12 +310,27 @@ fn word_parts_to_spans(parts: Vec<(String, Status)>, theme: &Theme) -> Vec<Span<
             Status::Overtyped => theme.prompt_incorrect,
         };
 
-        spans.push(Span::styled(text, style));
+        spans.push(Span::styled(visible(&text), style));
     }
     spans.push(Span::styled(" ", theme.prompt_untyped));
     spans
 }
 
+/// `text` with each grapheme that takes no column, like a combining mark typed
+/// without its letter, put on a dotted circle. The terminal skips graphemes without
+/// width, so the mark would neither show nor count in the layout of the line.
+fn visible(text: &str) -> String {
+    text.graphemes(true)
+        .map(|g| {
+            if g.width() == 0 && !g.chars().all(char::is_control) {
+                format!("{}{}", MARK_BASE, g)
+            } else {
+                g.to_string()
+            }
+        })
+        .collect()
+}
+
 impl ThemedWidget for &results::Results {
     fn render(self, area: Rect, buf: &mut Buffer, config: &Config) {
         let theme = &config.theme;
@@ -305,9 +338,14 @@ impl ThemedWidget for &results::Results {
         buf.set_style(area, theme.default);
 
         // Chunks
+        let error_height = self.save_error.is_some() as u16;
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(error_height),
+                Constraint::Length(1),
+            ])
             .split(area);
         let res_chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -326,11 +364,11 @@ impl ThemedWidget for &results::Results {
             )
             .split(res_chunks[0]);
 
-        let q = format_keybinding(&key_map.quit);
-        let r = format_keybinding(&key_map.restart);
-        let t = format_keybinding(&key_map.repeat);
-        let s = format_keybinding(&key_map.practice_slow);
-        let p = format_keybinding(&key_map.practice_missed);
+        let q = format_keybindings(&key_map.quit);
+        let r = format_keybindings(&key_map.restart);
+        let t = format_keybindings(&key_map.repeat);
+        let s = format_keybindings(&key_map.practice_slow);
+        let p = format_keybindings(&key_map.practice_missed);
         let msg = match (self.slow_words.is_empty(), self.missed_words.is_empty()) {
             (true, true) => format!("Press '{}' quit, '{}' new or '{}' repeat", q, r, t),
             (false, true) => {
@@ -354,7 +392,12 @@ impl ThemedWidget for &results::Results {
         };
 
         let exit = Span::styled(msg, theme.results_restart_prompt);
-        buf.set_span(chunks[1].x, chunks[1].y, &exit, chunks[1].width);
+        buf.set_span(chunks[2].x, chunks[2].y, &exit, chunks[2].width);
+
+        if let Some(error) = &self.save_error {
+            let error = Span::styled(error.as_str(), theme.prompt_incorrect);
+            buf.set_span(chunks[1].x, chunks[1].y, &error, chunks[1].width);
+        }
 
         // Sections
         let mut overview_text = Text::styled("", theme.results_overview);
@@ -373,6 +416,9 @@ impl ThemedWidget for &results::Results {
             )),
             Line::from(format!("Correct Keypresses: {}", self.accuracy.overall)),
         ]);
+        if let Some(code) = &self.code {
+            overview_text.extend([Line::from(format!("Test code: {}", code))]);
+        }
         let overview = Paragraph::new(overview_text)
             .block(
                 Block::default()
@@ -577,7 +623,7 @@ mod tests {
 
             for case in cases {
                 let (word, expected) = setup(case);
-                let got = split_typed_word(&word, false);
+                let got = split_typed_word(&word, Matching::default());
                 assert_eq!(got, expected);
             }
         }
@@ -589,7 +635,7 @@ mod tests {
                 .into_iter()
                 .map(TestWord::from)
                 .collect();
-            let spans = words_to_spans(&words, 0, &theme, false, None);
+            let spans = words_to_spans(&words, 0, &theme, Matching::default(), None);
             assert_eq!(
                 spans.len(),
                 5,
@@ -605,7 +651,7 @@ mod tests {
                 .map(TestWord::from)
                 .collect();
             // current_word=0, look_ahead=2: should show word 0 (current) + 2 upcoming = 3 total
-            let spans = words_to_spans(&words, 0, &theme, false, Some(2));
+            let spans = words_to_spans(&words, 0, &theme, Matching::default(), Some(2));
             assert_eq!(
                 spans.len(),
                 3,
@@ -616,10 +662,6 @@ mod tests {
         #[test]
         fn words_to_spans_look_ahead_one() {
             let theme = Theme::default();
-            let words: Vec<TestWord> = vec!["a", "b", "c", "d"]
-                .into_iter()
-                .map(TestWord::from)
-                .collect();
             // current_word=1, look_ahead=1: words[0] (typed) + word[1] (current) + word[2] (next) = 3
             let mut word0 = TestWord::from("a");
             word0.progress = "a".to_string();
@@ -629,7 +671,7 @@ mod tests {
                 TestWord::from("c"),
                 TestWord::from("d"),
             ];
-            let spans = words_to_spans(&words, 1, &theme, false, Some(1));
+            let spans = words_to_spans(&words, 1, &theme, Matching::default(), Some(1));
             assert_eq!(
                 spans.len(),
                 3,
@@ -642,7 +684,7 @@ mod tests {
             let theme = Theme::default();
             let words: Vec<TestWord> = vec!["a", "b"].into_iter().map(TestWord::from).collect();
             // current_word=0, look_ahead=10: only 1 upcoming word exists
-            let spans = words_to_spans(&words, 0, &theme, false, Some(10));
+            let spans = words_to_spans(&words, 0, &theme, Matching::default(), Some(10));
             assert_eq!(
                 spans.len(),
                 2,
@@ -658,7 +700,7 @@ mod tests {
                 .map(TestWord::from)
                 .collect();
             // look_ahead=0: show only the current word, no upcoming words
-            let spans = words_to_spans(&words, 0, &theme, false, Some(0));
+            let spans = words_to_spans(&words, 0, &theme, Matching::default(), Some(0));
             assert_eq!(
                 spans.len(),
                 1,
@@ -675,7 +717,7 @@ mod tests {
             word1.progress = "b".to_string();
             let words = vec![word0, word1, TestWord::from("c")];
             // current_word=2 (last word), look_ahead=5: no upcoming words to show
-            let spans = words_to_spans(&words, 2, &theme, false, Some(5));
+            let spans = words_to_spans(&words, 2, &theme, Matching::default(), Some(5));
             assert_eq!(
                 spans.len(),
                 3,
@@ -715,9 +757,79 @@ mod tests {
 
             for case in cases {
                 let (word, expected) = setup(case);
-                let got = split_current_word(&word, false);
+                let got = split_current_word(&word, Matching::default());
                 assert_eq!(got, expected);
             }
         }
+
+        #[test]
+        fn thai_and_emoji_words_split_by_grapheme() {
+            let cases = vec![
+                TestCase {
+                    word: "กับ",
+                    progress: "กีบ",
+                    expected: vec![("กั", Incorrect), ("บ", Correct)],
+                },
+                TestCase {
+                    word: "👍🏽ok",
+                    progress: "👍🏿ok",
+                    expected: vec![("👍🏽", Incorrect), ("ok", Correct)],
+                },
+            ];
+            for case in cases {
+                let (word, expected) = setup(case);
+                assert_eq!(split_typed_word(&word, Matching::default()), expected);
+            }
+
+            let (word, expected) = setup(TestCase {
+                word: "👩‍💻ok",
+                progress: "👩‍💻",
+                expected: vec![("👩‍💻", CurrentCorrect), ("o", Cursor), ("k", CurrentUntyped)],
+            });
+            assert_eq!(split_current_word(&word, Matching::default()), expected);
+        }
+
+        #[test]
+        fn lone_marks_are_drawn_on_a_base() {
+            assert_eq!(visible("กับ"), "กับ");
+            assert_eq!(visible("👍🏽"), "👍🏽");
+            assert_eq!(visible("\u{0E31}บ"), "\u{25CC}\u{0E31}บ");
+
+            let theme = Theme::default();
+            let spans = word_parts_to_spans(vec![("\u{0301}".to_string(), Overtyped)], &theme);
+            assert_eq!(spans[0].content, "\u{25CC}\u{0301}");
+            assert_eq!(spans[0].width(), 1);
+        }
+
+        #[test]
+        fn thai_words_split_by_grapheme() {
+            let thai = include_str!("../resources/runtime/language/thai");
+            let language = crate::language::Language::parse(thai).unwrap();
+            for text in &language.words {
+                let graphemes: Vec<&str> = text.graphemes(true).collect();
+                // Every prefix typed one character at a time, including those that end
+                // inside a grapheme, like a consonant before its vowel mark
+                for (end, _) in text.char_indices().skip(1) {
+                    let mut word = TestWord::from(text.as_str());
+                    word.progress = text[..end].to_string();
+                    let parts = split_current_word(&word, Matching::default());
+
+                    let shown: String = parts.iter().map(|(part, _)| part.as_str()).collect();
+                    assert_eq!(&shown, text);
+                    for (part, status) in &parts {
+                        assert_ne!(
+                            *status,
+                            CurrentIncorrect,
+                            "{} typed as {}",
+                            text,
+                            &text[..end]
+                        );
+                        if *status == Cursor {
+                            assert!(graphemes.contains(&part.as_str()), "{}", part);
+                        }
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,350 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Get path to the ttyper binary built by cargo.
+fn ttyper_bin() -> String {
+    env!("CARGO_BIN_EXE_ttyper").to_string()
+}
+
+/// Create a unique temp directory to avoid collisions with parallel test runs.
+fn unique_temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ttyper_{}_{}", name, std::process::id()))
+}
+
+/// Write a config pointing at a small history file and return the config path.
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let dir = unique_temp_dir(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let history = dir.join("history.csv");
+    fs::write(
+        &history,
+        "datetime,language,words,wpm_raw,wpm_adjusted,accuracy,correct,total,worst_keys\n\
+         2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,a:90%,\n\
+         2026-02-12 10:00:00,german,50,78.0,74.1,95.0,380,400,,\n",
+    )
+    .unwrap();
+    let config = dir.join("config.toml");
+    fs::write(
+        &config,
+        format!("history_file = {:?}\n", history.to_str().unwrap()),
+    )
+    .unwrap();
+    (dir, config)
+}
+
+fn run(config: &Path, args: &[&str]) -> Output {
+    Command::new(ttyper_bin())
+        .arg("-c")
+        .arg(config)
+        .args(args)
+        .output()
+        .expect("Failed to execute ttyper")
+}
+
+#[test]
+fn legacy_flags_match_subcommands() {
+    let (dir, config) = setup("cli_legacy");
+
+    let pairs: [(&[&str], &[&str]); 4] = [
+        (&["--history"], &["history"]),
+        (
+            &["--history", "--last", "1", "--history-lang", "german"],
+            &["history", "--last", "1", "--lang", "german"],
+        ),
+        (
+            &["--history", "--stats", "--group-by", "language"],
+            &["stats", "--group-by", "language"],
+        ),
+        (&["--list-languages"], &["languages"]),
+    ];
+    for (legacy, subcommand) in pairs {
+        let old = run(&config, legacy);
+        let new = run(&config, subcommand);
+        assert!(old.status.success(), "{:?} failed", legacy);
+        assert!(!old.stdout.is_empty());
+        assert_eq!(old.stdout, new.stdout, "{:?} vs {:?}", legacy, subcommand);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn history_only_flags_are_validated() {
+    let (dir, config) = setup("cli_validate");
+
+    for args in [
+        &["--last", "3"][..],
+        &["--history", "--keys"],
+        &["--history", "--since", "2026-13"],
+        &["-w", "10", "history"],
+        &["history", "--last", "2", "export"],
+        &["stats", "--group-by", "week", "--keys"],
+    ] {
+        let output = run(&config, args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{:?} should be rejected", args);
+        assert!(!stderr.contains("panicked"), "{:?}: {}", args, stderr);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn word_selection_flags_are_validated() {
+    let (dir, config) = setup("cli_selection");
+    let words = dir.join("words.txt");
+    fs::write(&words, "the\nof\nand\nhouse\n").unwrap();
+    let words = words.to_str().unwrap();
+
+    for args in [
+        &["--top", "5", "--range", "1..3"][..],
+        &["--range", "0..3"],
+        &["--range", "5..2"],
+        &["--range", "5"],
+        &["--top", "0"],
+        &[words, "--min-len", "3"],
+        &["--unlock", "neio"],
+        &["--letters", "arst", "--unlock-wpm", "40"],
+        &["--book"],
+        &[words, "--sentences", "2"],
+        &[words, "--book", "--pseudo"],
+    ] {
+        let output = run(&config, args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{:?} should be rejected", args);
+        assert!(!stderr.contains("panicked"), "{:?}: {}", args, stderr);
+    }
+
+    let output = run(
+        &config,
+        &[
+            "--language-file",
+            words,
+            "--range",
+            "2..3",
+            "--min-len",
+            "4",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("No words of language"), "{}", stderr);
+
+    let output = run(&config, &["--language-file", words, "--letters", "xyz"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("use only the letters 'xyz'"), "{}", stderr);
+
+    let notes = dir.join("notes.md");
+    fs::write(&notes, "```\ncode only\n```\n").unwrap();
+    let output = run(&config, &[notes.to_str().unwrap(), "--book"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("No words to type"), "{}", stderr);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn daily_stats_show_streak_and_scores() {
+    let (dir, config) = setup("cli_daily");
+    let history = dir.join("history.csv");
+    let mut content = fs::read_to_string(&history).unwrap();
+    content.push_str(
+        "2026-02-13 10:00:00,english1000,50,70.0,66.0,95.0,190,200,,,,,,,seed=20260213;daily=2026-02-13\n\
+         2026-02-14 10:00:00,english1000,50,75.0,71.5,96.0,190,200,,,,,,,seed=20260214;daily=2026-02-14\n",
+    );
+    fs::write(&history, content).unwrap();
+
+    for args in [
+        &["stats", "--daily"][..],
+        &["--history", "--stats", "--daily"],
+    ] {
+        let output = run(&config, args);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{:?}", args);
+        assert!(
+            stdout.contains("Daily challenge (2 days, since 2026-02-13)"),
+            "{}",
+            stdout
+        );
+        assert!(stdout.contains("Longest streak: 2 days"), "{}", stdout);
+        assert!(stdout.contains("+5.5"), "{}", stdout);
+    }
+    assert!(!run(&config, &["stats", "--daily", "--keys"])
+        .status
+        .success());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn global_options_work_before_and_after_subcommand() {
+    let (dir, config) = setup("cli_global");
+
+    let before = run(&config, &["history"]);
+    let after = Command::new(ttyper_bin())
+        .arg("history")
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .expect("Failed to execute ttyper");
+    assert!(before.status.success());
+    assert_eq!(before.stdout, after.stdout);
+    assert!(String::from_utf8_lossy(&before.stdout).contains("german"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn config_init_check_and_show() {
+    let dir = unique_temp_dir("cli_config");
+    let _ = fs::remove_dir_all(&dir);
+    let config = dir.join("nested").join("config.toml");
+
+    let init = run(&config, &["config", "init"]);
+    assert!(init.status.success());
+    assert!(config.exists(), "init creates missing directories");
+
+    let again = run(&config, &["config", "init"]);
+    assert!(String::from_utf8_lossy(&again.stderr).contains("--force"));
+
+    let check = run(&config, &["config", "check"]);
+    assert!(String::from_utf8_lossy(&check.stdout).contains("is valid"));
+
+    fs::write(&config, "[key_map]\nquit = \"r\"\nqiut = \"x\"\n").unwrap();
+    let check = run(&config, &["config", "check"]);
+    let warnings = String::from_utf8_lossy(&check.stderr).to_string();
+    assert!(
+        warnings.contains("unknown key 'key_map.qiut' at line 3"),
+        "{}",
+        warnings
+    );
+    assert!(warnings.contains("'quit' and 'restart'"), "{}", warnings);
+
+    fs::write(
+        &config,
+        "[key_map]\nquit = \"r\"\nqiut = \"x\"\nwords = [\n",
+    )
+    .unwrap();
+    let check = run(&config, &["config", "check"]);
+    assert!(!check.status.success(), "errors fail the check");
+    assert!(String::from_utf8_lossy(&check.stderr).contains("Error: "));
+    assert!(check.stdout.is_empty());
+    fs::write(&config, "[key_map]\nquit = \"r\"\nqiut = \"x\"\n").unwrap();
+
+    let show = String::from_utf8_lossy(&run(&config, &["config", "show"]).stdout).to_string();
+    assert!(show.contains("quit = \"r\""), "{}", show);
+    assert!(show.contains("prompt_correct = \"green\""), "{}", show);
+    let effective: toml::Value = toml::from_str(&show).expect("config show prints valid TOML");
+    assert!(effective
+        .get("history_file")
+        .is_some_and(toml::Value::is_str));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn named_themes_apply_and_unknown_ones_fall_back() {
+    let (dir, config) = setup("cli_theme");
+    fs::write(
+        &config,
+        "[theme]\ninherits = \"colorblind\"\nprompt_untyped = \"red\"\n",
+    )
+    .unwrap();
+
+    let show = String::from_utf8_lossy(&run(&config, &["config", "show"]).stdout).to_string();
+    assert!(
+        show.contains("prompt_incorrect = \"ff8700;underlined\""),
+        "{}",
+        show
+    );
+    assert!(show.contains("prompt_untyped = \"red\""), "{}", show);
+
+    let output = run(&config, &["config", "show", "--theme", "light"]);
+    let show = String::from_utf8_lossy(&output.stdout);
+    assert!(show.contains("default = \"303030:fafafa\""), "{}", show);
+
+    let output = run(&config, &["--theme", "nope", "config", "show"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Available themes:"), "{}", stderr);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("prompt_correct = \"green\""));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn environment_overrides_directories_and_history_moves_to_data_dir() {
+    let dir = unique_temp_dir("cli_env");
+    let _ = fs::remove_dir_all(&dir);
+    let (config_dir, data_dir) = (dir.join("config"), dir.join("data"));
+    let (languages, more_languages) = (dir.join("langs"), dir.join("more_langs"));
+    for d in [&config_dir, &languages, &more_languages] {
+        fs::create_dir_all(d).unwrap();
+    }
+    fs::write(
+        config_dir.join("history.csv"),
+        "datetime,language,words,wpm_raw,wpm_adjusted,accuracy,correct,total,worst_keys\n\
+         2026-02-10 10:00:00,english,50,72.0,68.4,95.0,190,200,,\n",
+    )
+    .unwrap();
+    fs::write(config_dir.join("config.toml"), "[key_map]\nquit = \"x\"\n").unwrap();
+    fs::write(languages.join("klingon"), "qapla\n").unwrap();
+    fs::write(more_languages.join("vulcan"), "shon\n").unwrap();
+
+    let ttyper = |args: &[&str]| {
+        let output = Command::new(ttyper_bin())
+            .env("TTYPER_CONFIG_DIR", &config_dir)
+            .env("TTYPER_DATA_DIR", &data_dir)
+            .env(
+                "TTYPER_LANGUAGE_PATH",
+                std::env::join_paths([&languages, &more_languages]).unwrap(),
+            )
+            .args(args)
+            .output()
+            .expect("Failed to execute ttyper");
+        (
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        )
+    };
+
+    // Showing the config leaves the old history where it is
+    let (stdout, stderr) = ttyper(&["config", "show"]);
+    assert!(stderr.is_empty(), "{}", stderr);
+    assert!(stdout.contains("history_file"), "{}", stdout);
+    assert!(config_dir.join("history.csv").exists());
+
+    let (stdout, stderr) = ttyper(&["history"]);
+    assert!(stderr.contains("Moved history"), "{}", stderr);
+    assert!(stdout.contains("english"), "{}", stdout);
+    assert!(!config_dir.join("history.csv").exists());
+    assert!(data_dir.join("history.csv").exists());
+
+    let (stdout, stderr) = ttyper(&["history"]);
+    assert!(stderr.is_empty(), "{}", stderr);
+    assert!(stdout.contains("english"), "{}", stdout);
+
+    fs::write(config_dir.join("history.csv"), "stale\n").unwrap();
+    let (stdout, stderr) = ttyper(&["history"]);
+    assert!(stderr.contains("Ignoring old history"), "{}", stderr);
+    assert!(stdout.contains("english"), "{}", stdout);
+    fs::remove_file(config_dir.join("history.csv")).unwrap();
+
+    let (stdout, _) = ttyper(&["config", "show"]);
+    assert!(stdout.contains("quit = \"x\""), "{}", stdout);
+
+    let (stdout, _) = ttyper(&["languages"]);
+    assert!(stdout.contains("klingon"), "{}", stdout);
+    assert!(stdout.contains("vulcan"), "{}", stdout);
+    assert!(stdout.contains("english"), "{}", stdout);
+    assert!(
+        stdout.contains("200 most common English words"),
+        "{}",
+        stdout
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -72,10 +72,10 @@ fn invalid_language_exits_cleanly() {
         stderr
     );
 
-    // Should suggest --list-languages
+    // Should suggest the languages subcommand
     assert!(
-        stderr.contains("--list-languages"),
-        "Expected suggestion to use --list-languages, got: {}",
+        stderr.contains("`ttyper languages`"),
+        "Expected suggestion to use `ttyper languages`, got: {}",
         stderr
     );
 }
@@ -118,7 +118,7 @@ fn binary_language_file_exits_cleanly() {
     fs::create_dir_all(&dir).unwrap();
     let file = dir.join("binary.bin");
     // Write invalid UTF-8 bytes
-    fs::write(&file, &[0xFF, 0xFE, 0x80, 0x81, 0x00, 0xC0, 0xC1]).unwrap();
+    fs::write(&file, [0xFF, 0xFE, 0x80, 0x81, 0x00, 0xC0, 0xC1]).unwrap();
 
     let output = Command::new(ttyper_bin())
         .arg("--language-file")